@@ -0,0 +1,177 @@
+// Compact Position Reporting, as used by airborne and surface position messages.  Positions are
+// encoded as 17-bit fractions of a latitude/longitude zone, alternating between even and odd
+// zone layouts; a single frame is ambiguous without either its counterpart or a nearby reference
+
+use std::f64::consts::PI;
+
+const NZ: f64 = 15.0;                       // Number of latitude zones between equator and pole
+const CPR_MAX: f64 = 131072.0;              // 2^17
+
+const AIRBORNE_EXTENT: f64 = 360.0;
+const SURFACE_EXTENT: f64 = 90.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CprPosition {
+    pub odd: bool,
+    pub lat: u32,                           // Raw 17-bit encoded latitude
+    pub lon: u32                            // Raw 17-bit encoded longitude
+}
+
+impl CprPosition {
+    fn lat_fraction(&self) -> f64 { self.lat as f64 / CPR_MAX }
+    fn lon_fraction(&self) -> f64 { self.lon as f64 / CPR_MAX }
+    fn parity(&self) -> f64 { if self.odd { 1.0 } else { 0.0 } }
+}
+
+// Number of longitude zones at the given latitude
+pub fn nl(lat: f64) -> u32 {
+    let lat = lat.abs();
+    if lat == 0.0 { return 59 }
+    if lat == 87.0 { return 2 }
+    if lat > 87.0 { return 1 }
+
+    let a = 1.0 - (PI / (2.0 * NZ)).cos();
+    let b = (PI / 180.0 * lat).cos().powi(2);
+    (2.0 * PI / (1.0 - a / b).acos()).floor() as u32
+}
+
+// Unambiguous decoding of an airborne even/odd pair, returning (lat, lon) for whichever frame was
+// received most recently.  Fails if the two frames straddle a longitude zone boundary
+pub fn global_airborne(even: &CprPosition, odd: &CprPosition, odd_is_newest: bool) -> Option<(f64, f64)> {
+    global(even, odd, odd_is_newest, AIRBORNE_EXTENT)
+        .map(|(lat, lon)| (lat, normalise_longitude(lon)))
+}
+
+// Global decoding of a surface pair is ambiguous to within 90 degrees; the candidate closest to
+// the reference position (typically the receiver location) is chosen
+pub fn global_surface(even: &CprPosition, odd: &CprPosition, odd_is_newest: bool, reference: (f64, f64)) -> Option<(f64, f64)> {
+    let (lat, lon) = global(even, odd, odd_is_newest, SURFACE_EXTENT)?;
+    let (ref_lat, ref_lon) = reference;
+
+    let lat = [lat, lat - 90.0].iter().cloned()
+        .min_by(|a, b| (a - ref_lat).abs().partial_cmp(&(b - ref_lat).abs()).unwrap())?;
+    let lon = [lon, lon + 90.0, lon + 180.0, lon + 270.0].iter()
+        .map(|&x| normalise_longitude(x))
+        .min_by(|a, b| angular_distance(*a, ref_lon).partial_cmp(&angular_distance(*b, ref_lon)).unwrap())?;
+
+    Some((lat, lon))
+}
+
+// Decoding of a single frame relative to a reference position, which must lie within half a
+// zone (~180NM airborne, ~45NM surface) of the true position
+pub fn local_airborne(position: &CprPosition, reference: (f64, f64)) -> (f64, f64) {
+    let (lat, lon) = local(position, reference, AIRBORNE_EXTENT);
+    (lat, normalise_longitude(lon))
+}
+
+pub fn local_surface(position: &CprPosition, reference: (f64, f64)) -> (f64, f64) {
+    let (lat, lon) = local(position, reference, SURFACE_EXTENT);
+    (lat, normalise_longitude(lon))
+}
+
+fn global(even: &CprPosition, odd: &CprPosition, odd_is_newest: bool, extent: f64) -> Option<(f64, f64)> {
+    let (dlat_even, dlat_odd) = (extent / 60.0, extent / 59.0);
+    let j = (59.0 * even.lat_fraction() - 60.0 * odd.lat_fraction() + 0.5).floor();
+
+    let lat_even = wrap_latitude(dlat_even * (modulo(j, 60.0) + even.lat_fraction()));
+    let lat_odd = wrap_latitude(dlat_odd * (modulo(j, 59.0) + odd.lat_fraction()));
+
+    if nl(lat_even) != nl(lat_odd) { return None }
+
+    let (lat, newest) = if odd_is_newest { (lat_odd, odd) } else { (lat_even, even) };
+    let nl_lat = nl(lat) as f64;
+    let ni = (nl_lat - newest.parity()).max(1.0);
+    let m = (even.lon_fraction() * (nl_lat - 1.0) - odd.lon_fraction() * nl_lat + 0.5).floor();
+    let lon = (extent / ni) * (modulo(m, ni) + newest.lon_fraction());
+
+    Some((lat, lon))
+}
+
+fn local(position: &CprPosition, reference: (f64, f64), extent: f64) -> (f64, f64) {
+    let (ref_lat, ref_lon) = reference;
+
+    let dlat = extent / (60.0 - position.parity());
+    let j = (ref_lat / dlat).floor() + (modulo(ref_lat, dlat) / dlat - position.lat_fraction() + 0.5).floor();
+    let lat = dlat * (j + position.lat_fraction());
+
+    let dlon = extent / (nl(lat) as f64 - position.parity()).max(1.0);
+    let m = (ref_lon / dlon).floor() + (modulo(ref_lon, dlon) / dlon - position.lon_fraction() + 0.5).floor();
+    let lon = dlon * (m + position.lon_fraction());
+
+    (lat, lon)
+}
+
+fn modulo(x: f64, y: f64) -> f64 {
+    x - y * (x / y).floor()
+}
+
+fn wrap_latitude(lat: f64) -> f64 {
+    if lat >= 270.0 { lat - 360.0 } else { lat }
+}
+
+fn normalise_longitude(lon: f64) -> f64 {
+    let lon = modulo(lon + 180.0, 360.0) - 180.0;
+    if lon == -180.0 { 180.0 } else { lon }
+}
+
+fn angular_distance(a: f64, b: f64) -> f64 {
+    let d = modulo(a - b, 360.0);
+    d.min(360.0 - d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adsb::ModeSFrame;
+    use crate::adsb::decoder::{self, AdsbMessage};
+
+    fn cpr(hex: &str) -> CprPosition {
+        match decoder::decode_message(&ModeSFrame::parse_hex(hex).unwrap().data) {
+            AdsbMessage::AirbornePosition { position, .. } => position,
+            AdsbMessage::SurfacePosition { position, .. } => position,
+            x => panic!("Not a position message: {:?}", x)
+        }
+    }
+
+    fn assert_position(actual: (f64, f64), expected: (f64, f64)) {
+        assert!((actual.0 - expected.0).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+        assert!((actual.1 - expected.1).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn test_longitude_zones() {
+        assert_eq!(nl(0.0), 59);
+        assert_eq!(nl(52.2572), 36);
+        assert_eq!(nl(-52.2572), 36);
+        assert_eq!(nl(87.0), 2);
+        assert_eq!(nl(89.0), 1);
+    }
+
+    #[test]
+    fn test_global_airborne() {
+        let even = cpr("8D40621D58C382D690C8AC2863A7");
+        let odd = cpr("8D40621D58C386435CC412692AD6");
+
+        assert_position(global_airborne(&even, &odd, false).unwrap(), (52.25720, 3.91937));
+        assert_position(global_airborne(&even, &odd, true).unwrap(), (52.26578, 3.93891));
+    }
+
+    #[test]
+    fn test_local_airborne() {
+        assert_position(local_airborne(&cpr("8D40621D58C382D690C8AC2863A7"), (52.258, 3.918)), (52.25720, 3.91937));
+    }
+
+    #[test]
+    fn test_global_surface() {
+        // Second frame received most recently
+        let (first, second) = (cpr("8C4841753AAB238733C8CD4020B1"), cpr("8C4841753A8A35323FAEBDAC702D"));
+        let (even, odd) = if first.odd { (second, first) } else { (first, second) };
+
+        assert_position(global_surface(&even, &odd, second.odd, (51.990, 4.375)).unwrap(), (52.32061, 4.73473));
+    }
+
+    #[test]
+    fn test_local_surface() {
+        assert_position(local_surface(&cpr("8FC8200A3AB8F5F893096B000000"), (-43.5, 172.5)), (-43.48564, 172.53942));
+    }
+}
@@ -0,0 +1,71 @@
+// Mode-S CRC-24, generator polynomial 0x1FFF409
+
+const GENERATOR: u32 = 0xFFF409;
+const PARITY_BYTES: usize = 3;
+
+const TABLE: [u32; 256] = build_table();
+
+const fn build_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = (i as u32) << 16;
+        let mut bit = 0;
+        while bit < 8 {
+            c = if c & 0x800000 != 0 { (c << 1) ^ GENERATOR } else { c << 1 };
+            bit += 1;
+        }
+        table[i] = c & 0xFFFFFF;
+        i += 1;
+    }
+    table
+}
+
+// CRC of the message content, i.e. everything other than the trailing 24 parity bits
+pub fn checksum(data: &[u8]) -> u32 {
+    data[..data.len().saturating_sub(PARITY_BYTES)].iter()
+        .fold(0u32, |crc, &b| ((crc << 8) ^ TABLE[(((crc >> 16) ^ b as u32) & 0xFF) as usize]) & 0xFFFFFF)
+}
+
+// Transmitted parity field XOR the computed checksum.  Zero for a valid extended squitter; for
+// formats with address/parity overlay this is the address of the transmitting aircraft
+pub fn residual(data: &[u8]) -> u32 {
+    if data.len() < PARITY_BYTES { return 0xFFFFFF }
+
+    let parity = data[(data.len() - PARITY_BYTES)..].iter()
+        .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+
+    parity ^ checksum(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checksum, residual};
+    use crate::adsb::ModeSFrame;
+
+    #[test]
+    fn test_valid_extended_squitter() {
+        let data = ModeSFrame::parse_hex("8D406B902015A678D4D220AA4BDA").unwrap().data;
+        assert_eq!(checksum(&data), 0xAA4BDA);
+        assert_eq!(residual(&data), 0);
+    }
+
+    #[test]
+    fn test_corrupt_extended_squitter() {
+        let mut data = ModeSFrame::parse_hex("8D4840D6202CC371C32CE0576098").unwrap().data;
+        assert_eq!(residual(&data), 0);
+
+        data[5] ^= 0x01;
+        assert_ne!(residual(&data), 0);
+    }
+
+    #[test]
+    fn test_address_parity_overlay() {
+        // DF20 replies; the residual recovers the address of the transmitting aircraft
+        let data = ModeSFrame::parse_hex("A0001839CA3800315800007448D9").unwrap().data;
+        assert_eq!(residual(&data), 0x400940);
+
+        let data = ModeSFrame::parse_hex("A000139381951536E024D4CCF6B5").unwrap().data;
+        assert_eq!(residual(&data), 0x3C4DD2);
+    }
+}
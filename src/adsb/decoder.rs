@@ -0,0 +1,284 @@
+use std::f32::consts::PI;
+use std::fmt;
+use crate::adsb::{crc, ModeSFrame};
+use crate::adsb::cpr::CprPosition;
+
+const EXTENDED_SQUITTER_LENGTH: usize = 14;
+const ME_OFFSET: usize = 32;                // Message field follows DF/CA (8 bits) and address (24 bits)
+
+const CALLSIGN_CHARSET: &[u8; 64] = b"#ABCDEFGHIJKLMNOPQRSTUVWXYZ##### ###############0123456789######";
+
+// Decoded DF17/DF18 extended squitter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendedSquitter {
+    pub icao24: String,
+    pub type_code: u8,
+    pub message: AdsbMessage
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdsbMessage {
+    Identification {
+        category: u8,
+        callsign: String
+    },
+    AirbornePosition {
+        position: CprPosition,
+        altitude: Option<f32>,              // Feet; barometric, or GNSS height if `gnss` is set
+        gnss: bool
+    },
+    SurfacePosition {
+        position: CprPosition,
+        ground_speed: Option<f32>,          // Knots
+        track: Option<f32>                  // Decimal degrees clockwise from N
+    },
+    Velocity {
+        speed: Option<f32>,                 // Knots; ground speed, or airspeed if `airspeed` is set
+        track: Option<f32>,                 // Decimal degrees clockwise from N; heading if `airspeed` is set
+        vertical_rate: Option<f32>,         // Feet per minute, positive means climbing
+        airspeed: bool
+    },
+    Other
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    Length(usize),
+    UnsupportedFormat(u8),
+    Checksum(u32)
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Length(n) => write!(f, "Invalid extended squitter length ({} bytes)", n),
+            DecodeError::UnsupportedFormat(df) => write!(f, "Unsupported downlink format (DF{})", df),
+            DecodeError::Checksum(residual) => write!(f, "CRC check failed (residual {:06x})", residual)
+        }
+    }
+}
+
+// Validates and decodes an ADS-B extended squitter.  Only DF17, and DF18 with an ADS-B control
+// field, are supported; frames failing the CRC check are rejected rather than corrected
+pub fn decode(frame: &ModeSFrame) -> Result<ExtendedSquitter, DecodeError> {
+    let data = &frame.data;
+    if data.len() != EXTENDED_SQUITTER_LENGTH { return Err(DecodeError::Length(data.len())) }
+
+    let df = frame.downlink_format();
+    let control = data[0] & 0x07;
+    if !(df == 17 || (df == 18 && control <= 1)) { return Err(DecodeError::UnsupportedFormat(df)) }
+
+    let residual = crc::residual(data);
+    if residual != 0 { return Err(DecodeError::Checksum(residual)) }
+
+    Ok(ExtendedSquitter {
+        icao24: format!("{:02x}{:02x}{:02x}", data[1], data[2], data[3]),
+        type_code: type_code(data),
+        message: decode_message(data)
+    })
+}
+
+// Decodes the message field of an extended squitter, without any validation of the frame
+pub fn decode_message(data: &[u8]) -> AdsbMessage {
+    match type_code(data) {
+        1..=4 => decode_identification(data),
+        5..=8 => decode_surface_position(data),
+        9..=18 => decode_airborne_position(data, false),
+        19 => decode_velocity(data),
+        20..=22 => decode_airborne_position(data, true),
+        _ => AdsbMessage::Other
+    }
+}
+
+fn type_code(data: &[u8]) -> u8 {
+    me(data, 1, 5) as u8
+}
+
+fn decode_identification(data: &[u8]) -> AdsbMessage {
+    let callsign = (0..8)
+        .map(|i| CALLSIGN_CHARSET[me(data, 9 + i * 6, 6) as usize] as char)
+        .collect::<String>();
+
+    AdsbMessage::Identification {
+        category: me(data, 6, 3) as u8,
+        callsign: callsign.trim_end().to_string()
+    }
+}
+
+fn decode_airborne_position(data: &[u8], gnss: bool) -> AdsbMessage {
+    let raw_altitude = me(data, 9, 12);
+
+    AdsbMessage::AirbornePosition {
+        position: cpr_position(data),
+        altitude: if gnss { gnss_altitude(raw_altitude) } else { barometric_altitude(raw_altitude) },
+        gnss
+    }
+}
+
+fn decode_surface_position(data: &[u8]) -> AdsbMessage {
+    AdsbMessage::SurfacePosition {
+        position: cpr_position(data),
+        ground_speed: surface_movement(me(data, 6, 7)),
+        track: if me(data, 13, 1) == 1 { Some(me(data, 14, 7) as f32 * 360.0 / 128.0) } else { None }
+    }
+}
+
+fn decode_velocity(data: &[u8]) -> AdsbMessage {
+    let subtype = me(data, 6, 3);
+    let multiplier = if subtype == 2 || subtype == 4 { 4.0 } else { 1.0 };     // Supersonic
+
+    let vertical_rate = match me(data, 38, 9) {
+        0 => None,
+        raw => Some((raw as f32 - 1.0) * 64.0 * if me(data, 37, 1) == 1 { -1.0 } else { 1.0 })
+    };
+
+    match subtype {
+        1 | 2 => {
+            let (raw_ew, raw_ns) = (me(data, 15, 10), me(data, 26, 10));
+            let (speed, track) = if raw_ew == 0 || raw_ns == 0 { (None, None) } else {
+                let v_ew = (raw_ew as f32 - 1.0) * multiplier * if me(data, 14, 1) == 1 { -1.0 } else { 1.0 };
+                let v_ns = (raw_ns as f32 - 1.0) * multiplier * if me(data, 25, 1) == 1 { -1.0 } else { 1.0 };
+
+                let track = v_ew.atan2(v_ns) * 180.0 / PI;
+                (Some(v_ew.hypot(v_ns)), Some(if track < 0.0 { track + 360.0 } else { track }))
+            };
+
+            AdsbMessage::Velocity { speed, track, vertical_rate, airspeed: false }
+        },
+        3 | 4 => {
+            let heading = if me(data, 14, 1) == 1 { Some(me(data, 15, 10) as f32 * 360.0 / 1024.0) } else { None };
+            let speed = match me(data, 26, 10) {
+                0 => None,
+                raw => Some((raw as f32 - 1.0) * multiplier)
+            };
+
+            AdsbMessage::Velocity { speed, track: heading, vertical_rate, airspeed: true }
+        },
+        _ => AdsbMessage::Other
+    }
+}
+
+fn cpr_position(data: &[u8]) -> CprPosition {
+    CprPosition {
+        odd: me(data, 22, 1) == 1,
+        lat: me(data, 23, 17),
+        lon: me(data, 40, 17)
+    }
+}
+
+// 12-bit altitude code.  Only 25ft increments (Q-bit set) are decoded; Gillham-coded 100ft
+// increments are only used above 50,175ft and are reported as unknown
+fn barometric_altitude(raw: u32) -> Option<f32> {
+    if raw == 0 || raw & 0x10 == 0 { return None }
+
+    let n = ((raw & 0xFE0) >> 1) | (raw & 0x0F);
+    Some(n as f32 * 25.0 - 1000.0)
+}
+
+fn gnss_altitude(raw: u32) -> Option<f32> {
+    if raw == 0 { None } else { Some(raw as f32 / 0.3048) }      // Encoded in metres
+}
+
+// Surface movement field; non-linear ground speed encoding in knots
+fn surface_movement(raw: u32) -> Option<f32> {
+    const STEPS: [(u32, f32, f32); 7] = [       // (First code, speed at that code, increment per code)
+        (1, 0.0, 0.0), (2, 0.125, 0.125), (9, 1.0, 0.25), (13, 2.0, 0.5),
+        (39, 15.0, 1.0), (94, 70.0, 2.0), (109, 100.0, 5.0)
+    ];
+
+    match raw {
+        0 | 125..=127 => None,
+        124 => Some(175.0),
+        _ => STEPS.iter().rev()
+            .find(|(start, _, _)| raw >= *start)
+            .map(|(start, base, step)| base + (raw - start) as f32 * step)
+    }
+}
+
+// Extracts `length` bits from the ME field, where `start` is the 1-based ME bit number
+fn me(data: &[u8], start: usize, length: usize) -> u32 {
+    bits(data, ME_OFFSET + start - 1, length)
+}
+
+fn bits(data: &[u8], start: usize, length: usize) -> u32 {
+    (start..(start + length))
+        .fold(0u32, |acc, bit| (acc << 1) | ((data[bit / 8] >> (7 - (bit % 8))) & 0x01) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_message, AdsbMessage, DecodeError};
+    use crate::adsb::ModeSFrame;
+
+    fn frame(hex: &str) -> ModeSFrame {
+        ModeSFrame::parse_hex(hex).unwrap()
+    }
+
+    #[test]
+    fn test_identification() {
+        let es = decode(&frame("8D4840D6202CC371C32CE0576098")).unwrap();
+        assert_eq!(es.icao24, "4840d6");
+        assert_eq!(es.type_code, 4);
+        assert_eq!(es.message, AdsbMessage::Identification { category: 0, callsign: "KLM1023".to_string() });
+    }
+
+    #[test]
+    fn test_airborne_position() {
+        let es = decode(&frame("8D40621D58C382D690C8AC2863A7")).unwrap();
+        assert_eq!(es.icao24, "40621d");
+        assert_eq!(es.type_code, 11);
+        match es.message {
+            AdsbMessage::AirbornePosition { position, altitude, gnss } => {
+                assert!(!position.odd);
+                assert_eq!((position.lat, position.lon), (93000, 51372));
+                assert_eq!(altitude, Some(38000.0));
+                assert!(!gnss);
+            },
+            x => panic!("Unexpected message {:?}", x)
+        }
+    }
+
+    #[test]
+    fn test_ground_velocity() {
+        match decode(&frame("8D485020994409940838175B284F")).unwrap().message {
+            AdsbMessage::Velocity { speed, track, vertical_rate, airspeed } => {
+                assert!((speed.unwrap() - 159.20).abs() < 0.01);
+                assert!((track.unwrap() - 182.88).abs() < 0.01);
+                assert_eq!(vertical_rate, Some(-832.0));
+                assert!(!airspeed);
+            },
+            x => panic!("Unexpected message {:?}", x)
+        }
+    }
+
+    #[test]
+    fn test_airspeed_velocity() {
+        match decode(&frame("8DA05F219B06B6AF189400CBC33F")).unwrap().message {
+            AdsbMessage::Velocity { speed, track, vertical_rate, airspeed } => {
+                assert_eq!(speed, Some(375.0));
+                assert!((track.unwrap() - 243.98).abs() < 0.01);
+                assert_eq!(vertical_rate, Some(-2304.0));
+                assert!(airspeed);
+            },
+            x => panic!("Unexpected message {:?}", x)
+        }
+    }
+
+    #[test]
+    fn test_surface_position() {
+        match decode_message(&frame("8C4841753A9A153237AEF0F275BE").data) {
+            AdsbMessage::SurfacePosition { ground_speed, track, .. } => {
+                assert_eq!(ground_speed, Some(17.0));
+                assert!((track.unwrap() - 92.8125).abs() < 0.001);
+            },
+            x => panic!("Unexpected message {:?}", x)
+        }
+    }
+
+    #[test]
+    fn test_rejected_frames() {
+        assert_eq!(decode(&frame("8D4840D6202CC371C32CE0576099")), Err(DecodeError::Checksum(0x000001)));
+        assert_eq!(decode(&frame("5D484FDEA248F5")), Err(DecodeError::Length(7)));
+        assert_eq!(decode(&frame("A0001839CA3800315800007448D9")), Err(DecodeError::UnsupportedFormat(20)));
+    }
+}
@@ -0,0 +1,229 @@
+pub mod crc;
+pub mod cpr;
+pub mod decoder;
+
+use std::collections::HashMap;
+use crate::adsb::cpr::CprPosition;
+use crate::adsb::decoder::{AdsbMessage, ExtendedSquitter};
+use crate::sources::receiver::ReceiverState;
+use crate::util::units::{Altitude, Speed, VerticalRate};
+
+const CPR_PAIR_MAX_AGE_SECS: i64 = 10;      // Even/odd frames further apart than this are not paired
+const MLAT_TIMESTAMP_LENGTH: usize = 12;    // Hex digits preceding the data in '@' AVR lines
+
+// Raw Mode-S message as received from a local receiver, with any metadata the receiver provides
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModeSFrame {
+    pub data: Vec<u8>,                      // 7 bytes (short) or 14 bytes (long)
+    pub timestamp: Option<u64>,             // Receiver clock; 12MHz counter for Beast/MLAT receivers
+    pub rssi: Option<f32>                   // Signal level, dBFS
+}
+
+impl ModeSFrame {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data, timestamp: None, rssi: None }
+    }
+
+    // Frame from its hex representation, e.g. "8D4840D6202CC371C32CE0576098"
+    pub fn parse_hex(hex: &str) -> Option<Self> {
+        if !(hex.len() == 14 || hex.len() == 28) || !hex.is_ascii() { return None }
+
+        (0..hex.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..(i + 2)], 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .map(ModeSFrame::new)
+    }
+
+    // Frame from an AVR format line: "*<hex>;", or "@<timestamp><hex>;" with a 48-bit MLAT timestamp
+    pub fn parse_avr(line: &str) -> Option<Self> {
+        let line = line.trim().trim_end_matches(';');
+        if !line.is_ascii() { return None }
+
+        match line.chars().next() {
+            Some('*') => Self::parse_hex(&line[1..]),
+            Some('@') if line.len() > MLAT_TIMESTAMP_LENGTH + 1 => {
+                let (timestamp, hex) = line[1..].split_at(MLAT_TIMESTAMP_LENGTH);
+                Self::parse_hex(hex).map(|frame| Self {
+                    timestamp: u64::from_str_radix(timestamp, 16).ok(),
+                    ..frame
+                })
+            },
+            _ => None
+        }
+    }
+
+    pub fn downlink_format(&self) -> u8 {
+        (self.data[0] >> 3).min(24)         // All formats 24..31 are DF24 (Comm-D)
+    }
+
+    // Address of the transmitting aircraft, for formats which carry it in the clear.  All other
+    // formats overlay the address on the parity bits
+    pub fn icao_address(&self) -> Option<String> {
+        match self.downlink_format() {
+            11 | 17 | 18 if self.data.len() >= 4 =>
+                Some(format!("{:02x}{:02x}{:02x}", self.data[1], self.data[2], self.data[3])),
+            _ => None
+        }
+    }
+}
+
+// Decodes frames from a local receiver into aircraft state, retaining the per-aircraft history
+// needed to resolve CPR positions
+pub struct AdsbTracker {
+    reference: Option<(f64, f64)>,          // Receiver location (lat, lon), if known
+    cpr: HashMap<String, CprFrames>
+}
+
+#[derive(Default)]
+struct CprFrames {
+    surface: bool,
+    even: Option<(CprPosition, i64)>,
+    odd: Option<(CprPosition, i64)>
+}
+
+impl AdsbTracker {
+    pub fn new(reference: Option<(f64, f64)>) -> Self {
+        Self { reference, cpr: HashMap::new() }
+    }
+
+    // Applies a received frame to the aircraft state maintained for a local receiver
+    pub fn apply(&mut self, state: &mut ReceiverState, frame: &ModeSFrame, time: i64) {
+        match frame.downlink_format() {
+            17 | 18 => if let Ok(es) = decoder::decode(frame) {
+                self.apply_squitter(state, &es, time);
+            },
+            11 if crc::residual(&frame.data) & !0x7F == 0 => {        // Parity overlaid with interrogator ID
+                if let Some(icao24) = frame.icao_address() {
+                    state.update(&icao24, time, |_| ());
+                }
+            },
+            _ => ()
+        }
+    }
+
+    fn apply_squitter(&mut self, state: &mut ReceiverState, es: &ExtendedSquitter, time: i64) {
+        let last_position = state.get(&es.icao24)
+            .and_then(|x| x.latitude.and_then(|lat| x.longitude.map(|lon| (lat, lon))));
+
+        match &es.message {
+            AdsbMessage::Identification { callsign, .. } => state.update(&es.icao24, time, |x| {
+                x.callsign = Some(callsign.clone());
+            }),
+
+            AdsbMessage::AirbornePosition { position, altitude, gnss } => {
+                let resolved = self.resolve_position(&es.icao24, position, false, time, last_position);
+                state.update(&es.icao24, time, |x| {
+                    let altitude = altitude.map(Altitude::from_feet);
+                    if *gnss { x.geo_altitude = altitude } else { x.baro_altitude = altitude }
+
+                    x.on_ground = false;
+                    if let Some((lat, lon)) = resolved {
+                        x.latitude = Some(lat);
+                        x.longitude = Some(lon);
+                        x.time_position = Some(time);
+                    }
+                })
+            },
+
+            AdsbMessage::SurfacePosition { position, ground_speed, track } => {
+                let resolved = self.resolve_position(&es.icao24, position, true, time, last_position);
+                state.update(&es.icao24, time, |x| {
+                    x.on_ground = true;
+                    x.velocity = ground_speed.map(Speed::from_knots);
+                    if track.is_some() { x.true_track = *track }
+                    if let Some((lat, lon)) = resolved {
+                        x.latitude = Some(lat);
+                        x.longitude = Some(lon);
+                        x.time_position = Some(time);
+                    }
+                })
+            },
+
+            AdsbMessage::Velocity { speed, track, vertical_rate, .. } => state.update(&es.icao24, time, |x| {
+                x.velocity = speed.map(Speed::from_knots);
+                x.true_track = *track;
+                x.vertical_rate = vertical_rate.map(VerticalRate::from_feet_per_min);
+            }),
+
+            AdsbMessage::Other => state.update(&es.icao24, time, |_| ())
+        }
+    }
+
+    // Globally decodes the position if a recent frame of the opposite parity is available, otherwise
+    // decodes locally relative to the last known position of the aircraft or the receiver location
+    fn resolve_position(&mut self, icao24: &str, position: &CprPosition, surface: bool, time: i64,
+                        last_position: Option<(f64, f64)>) -> Option<(f64, f64)> {
+        let frames = self.cpr.entry(icao24.to_string()).or_default();
+        if frames.surface != surface {
+            *frames = CprFrames { surface, ..CprFrames::default() };
+        }
+
+        if position.odd { frames.odd = Some((*position, time)) } else { frames.even = Some((*position, time)) }
+
+        let reference = last_position.or(self.reference);
+        if let (Some((even, t_even)), Some((odd, t_odd))) = (frames.even, frames.odd) {
+            if (t_even - t_odd).abs() <= CPR_PAIR_MAX_AGE_SECS {
+                let global = if surface {
+                    reference.and_then(|r| cpr::global_surface(&even, &odd, position.odd, r))
+                } else {
+                    cpr::global_airborne(&even, &odd, position.odd)
+                };
+
+                if global.is_some() { return global }
+            }
+        }
+
+        reference.map(|r| if surface { cpr::local_surface(position, r) } else { cpr::local_airborne(position, r) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdsbTracker, ModeSFrame};
+    use crate::sources::receiver::ReceiverState;
+
+    #[test]
+    fn test_parse_avr() {
+        let frame = ModeSFrame::parse_avr("*8D4840D6202CC371C32CE0576098;").unwrap();
+        assert_eq!(frame.data.len(), 14);
+        assert_eq!(frame.timestamp, None);
+
+        let frame = ModeSFrame::parse_avr("@00002F4C82195D484FDEA248F5;").unwrap();
+        assert_eq!(frame.data, vec![0x5d, 0x48, 0x4f, 0xde, 0xa2, 0x48, 0xf5]);
+        assert_eq!(frame.timestamp, Some(0x2f4c8219));
+
+        assert!(ModeSFrame::parse_avr("*8D4840D6;").is_none());
+        assert!(ModeSFrame::parse_avr("8D4840D6202CC371C32CE0576098").is_none());
+    }
+
+    #[test]
+    fn test_tracker_builds_state_vector() {
+        let (mut tracker, mut state) = (AdsbTracker::new(None), ReceiverState::new(60));
+        let mut apply = |hex: &str, time: i64| tracker.apply(&mut state, &ModeSFrame::parse_hex(hex).unwrap(), time);
+
+        apply("8D40621D58C382D690C8AC2863A7", 100);
+        apply("8D40621D58C386435CC412692AD6", 98);        // Odd frame (arriving second, with earlier time)
+        apply("8D40621D99440994083817000000", 101);       // Corrupt velocity message is ignored
+        apply("8D4840D6202CC371C32CE0576098", 102);
+
+        let aircraft = state.get("40621d").unwrap();
+        assert!((aircraft.latitude.unwrap() - 52.26578).abs() < 1e-4);
+        assert!((aircraft.longitude.unwrap() - 3.93891).abs() < 1e-4);
+        assert!((aircraft.baro_altitude.unwrap().metres() - 11582.4).abs() < 0.01);
+        assert_eq!(aircraft.velocity, None);
+        assert_eq!(aircraft.time_position, Some(98));
+        assert_eq!(aircraft.last_contact, 100);
+
+        assert_eq!(state.get("4840d6").unwrap().callsign, Some("KLM1023".to_string()));
+    }
+
+    #[test]
+    fn test_tracker_local_decoding_from_reference() {
+        let (mut tracker, mut state) = (AdsbTracker::new(Some((52.258, 3.918))), ReceiverState::new(60));
+        tracker.apply(&mut state, &ModeSFrame::parse_hex("8D40621D58C382D690C8AC2863A7").unwrap(), 100);
+
+        let aircraft = state.get("40621d").unwrap();
+        assert!((aircraft.latitude.unwrap() - 52.25720).abs() < 1e-4);
+        assert!((aircraft.longitude.unwrap() - 3.91937).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+use crate::data::trails::{TrailRetention, DEFAULT_TRAIL_RETENTION};
+use crate::util::units::UnitSystem;
+use crate::sources::basestation::{self, BaseStationSource};
+use crate::sources::beast::{self, BeastSource};
+use crate::sources::avr::{self, AvrSource};
+use crate::sources::dump1090::Dump1090Source;
+use crate::sources::recording::ReplaySource;
+use crate::sources::state_vectors::StateVectorSource;
+
+const DEFAULT_MAX_EXTRAPOLATION: Duration = Duration::from_secs(30);
+
+// Command-line options, of the form "--name value" or "--flag"
+pub struct Arguments {
+    args: Vec<String>
+}
+
+impl Arguments {
+    pub fn from_env() -> Self {
+        Self { args: std::env::args().skip(1).collect() }
+    }
+
+    pub fn value(&self, name: &str) -> Option<String> {
+        self.args.iter()
+            .position(|x| x == name)
+            .and_then(|ix| self.args.get(ix + 1))
+            .cloned()
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.args.iter().any(|x| x == name)
+    }
+
+    // Receiver location, given as "lat,lon"
+    pub fn location(&self) -> Option<(f64, f64)> {
+        self.value("--location").and_then(|x| {
+            let parts = x.split(',').map(|p| p.trim().parse::<f64>().ok()).collect::<Vec<Option<f64>>>();
+            match parts.as_slice() {
+                [Some(lat), Some(lon)] => Some((*lat, *lon)),
+                _ => { eprintln!("Ignoring invalid receiver location \"{}\"", x); None }
+            }
+        })
+    }
+
+    // Initial playback speed for recorded sessions
+    pub fn playback_speed(&self) -> f64 {
+        self.value("--replay-speed").and_then(|x| x.parse::<f64>().ok()).unwrap_or(1.0)
+    }
+
+    // Position history kept for each aircraft, as either "--trail-points n" or "--trail-minutes n"
+    pub fn trail_retention(&self) -> TrailRetention {
+        let parse = |name| self.value(name).and_then(|x| x.parse::<u32>().map_err(|_| eprintln!("Ignoring invalid {} \"{}\"", name, x)).ok());
+
+        match (parse("--trail-points"), parse("--trail-minutes")) {
+            (Some(points), _) => TrailRetention::Points(points as usize),
+            (None, Some(minutes)) => TrailRetention::Minutes(minutes),
+            (None, None) => DEFAULT_TRAIL_RETENTION
+        }
+    }
+
+    // Furthest aircraft positions are extrapolated beyond their last report, in seconds; zero to disable
+    pub fn max_extrapolation(&self) -> Duration {
+        self.value("--max-extrapolation")
+            .and_then(|x| x.parse::<u64>().map_err(|_| eprintln!("Ignoring invalid --max-extrapolation \"{}\"", x)).ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_MAX_EXTRAPOLATION)
+    }
+
+    // Unit system for display, as "--units aviation" or "--units metric"
+    pub fn units(&self) -> UnitSystem {
+        self.value("--units")
+            .and_then(|x| UnitSystem::parse(&x).or_else(|| { eprintln!("Ignoring unknown unit system \"{}\"", x); None }))
+            .unwrap_or(UnitSystem::Aviation)
+    }
+
+    pub fn values(&self, name: &str) -> Vec<String> {
+        self.args.iter()
+            .zip(self.args.iter().skip(1))
+            .filter(|(x, _)| *x == name)
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    // Returns all state vector sources selected on the command line.  Each receiver option may be repeated
+    pub fn state_sources(&self) -> Vec<Box<dyn StateVectorSource>> {
+        let mut sources: Vec<Box<dyn StateVectorSource>> = vec![];
+
+        for address in self.values("--sbs") {
+            sources.push(Box::new(BaseStationSource::connect(&with_default_port(address, basestation::DEFAULT_PORT))));
+        }
+        for address in self.values("--beast") {
+            sources.push(Box::new(BeastSource::connect(&with_default_port(address, beast::DEFAULT_PORT), self.location())));
+        }
+        for address in self.values("--avr") {
+            sources.push(Box::new(AvrSource::connect(&with_default_port(address, avr::DEFAULT_PORT), self.location())));
+        }
+        for location in self.values("--dump1090") {
+            sources.push(Box::new(Dump1090Source::new(&location)));
+        }
+        if let Some(path) = self.value("--replay") {
+            sources.push(Box::new(ReplaySource::load(&path)
+                .unwrap_or_else(|e| panic!("Failed to load replay session \"{}\" ({})", path, e))));
+        }
+
+        sources
+    }
+}
+
+fn with_default_port(address: String, port: u16) -> String {
+    if address.contains(':') { address } else { format!("{}:{}", address, port) }
+}
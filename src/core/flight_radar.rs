@@ -1,23 +1,43 @@
 extern crate piston_window;
 use std::thread;
+use std::time::{Duration, Instant};
 use std::sync::mpsc;
 use std::sync::mpsc::{Sender, Receiver};
 use ::image;
 use piston_window::*;
-use sources::sources::{Source, SourceProvider};
+use sources::sources::SourceProvider;
+use sources::state_vectors::StateVectorSource;
+use sources::opensky::OpenSkySource;
+use sources::fusion::FusionSource;
+use sources::credentials::{Credentials, CredentialProvider};
+use sources::recording::{RecordingSource, SessionRecorder};
 use crate::data;
 use crate::sources;
 use crate::simulation;
+use crate::simulation::clock::SimulationClock;
+use crate::simulation::{SimulationTrigger, PlaybackCommand, SimulationStatus, FlightDataRequest, FlightDataResponse};
 use crate::rendering;
 use crate::data::geography;
-use crate::data::aircraft::{Aircraft, AircraftData};
-use crate::data::flight::FlightData;
+use crate::data::aircraft::{Aircraft, Extrapolation};
+use crate::data::store::{AircraftStore, AircraftEvent};
+use crate::data::trails::{TrailStore, TrailRetention};
+use crate::data::alerts::AlertMonitor;
+use crate::data::aircraft_database::{self, AircraftDatabase};
+use crate::data::airlines::{self, AirlineTable};
+use crate::data::airports::{self, Airport, AirportDatabase};
+use crate::data::airport_board::{AirportBoard, BoardEntry, Movement};
+use crate::data::search::AircraftSearch;
+use crate::data::flight::{self as flights, Flight, FlightData, Track};
 use crate::rendering::BackBuffer;
 use crate::text;
 use std::cell::{RefCell, Ref, RefMut};
-use crate::geo::coords::{lon_lat_to_map, normalise_to_window, normalised_coords};
-use crate::rendering::colour::{COLOUR_SELECTED_OBJECT, COLOUR_STATUS_AREA_BACK, COLOUR_STATUS_AREA_OUTLINE, COLOUR_STATUS_AREA_TEXT};
-use crate::util::temporal::get_current_timestamp_secs;
+use crate::geo::bounds::GeoBounds;
+use crate::geo::greatcircle;
+use crate::geo::coords::{in_bounds, lon_lat_to_map, normalise_to_window, normalised_coords};
+use crate::rendering::colour::{COLOUR_AIRPORT_LABEL, COLOUR_ALERT, COLOUR_BOARD_PANEL_BACK, COLOUR_FLIGHT_PANEL_BACK, COLOUR_ALERT_PANEL_BACK, COLOUR_SEARCH_PANEL_BACK, COLOUR_SELECTED_OBJECT, COLOUR_STATUS_AREA_BACK, COLOUR_STATUS_AREA_OUTLINE, COLOUR_STATUS_AREA_TEXT,
+                               COLOUR_STATUS_AREA_WARNING, COLOUR_TIMELINE_BACK, COLOUR_TIMELINE_PROGRESS};
+use crate::util::temporal::{get_current_timestamp_secs, utc_datetime_from_timestamp};
+use crate::util::units::{Distance, UnitSystem};
 
 const MOUSE_LEFT: usize = 0;
 const MOUSE_RIGHT: usize = 1;
@@ -29,14 +49,45 @@ const PAN_SCALING_FACTOR: f64 = 1.5;
 const MAX_OBJECT_SELECT_DISTANCE_SQ: f64 = 2.0 * 2.0;
 const SELECTION_CIRCLE_RADIUS: f64 = 5.0;
 const STATUS_AREA_SIZE: f64 = 0.1;
+const TIMELINE_BAR: [f64; 4] = [0.01, 1.0 - 0.03, 0.98, 0.012];
+const TIMELINE_CLICK_MARGIN: f64 = 0.01;
+const PLAYBACK_SPEEDS: [f64; 4] = [1.0, 4.0, 16.0, 60.0];
+const BACKBUFFER_REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+const ALERT_CIRCLE_RADIUS: f64 = 8.0;
+const ALERT_PANEL: [f64; 4] = [0.70, 0.04, 0.29, 0.025];     // [x, y, width, line spacing]
+const MAX_ALERT_PANEL_ENTRIES: usize = 10;
+const SEARCH_PANEL: [f64; 4] = [0.01, 0.04, 0.45, 0.025];    // [x, y, width, line spacing]
+const MAX_SEARCH_PANEL_ENTRIES: usize = 10;
+const FLIGHT_PANEL: [f64; 3] = [0.50, 0.49, 0.022];           // [x, width, line spacing]; above the status area
+const MAX_FLIGHT_PANEL_ENTRIES: usize = 8;
+const MIN_ETA_SPEED: f32 = 25.0;                            // m/s; no arrival time is estimated for slower aircraft
+const BOARD_PANEL: [f64; 4] = [0.01, 0.40, 0.48, 0.022];     // [x, y, width, line spacing]
+const MAX_BOARD_PANEL_ENTRIES: usize = 8;
+const AIRPORT_SELECT_RADIUS: f64 = 12.0;                    // Pixels
+const AIRPORT_LABEL_MIN_ZOOM: f64 = 8.0;
+const AIRPORT_DETAIL_MIN_ZOOM: f64 = 64.0;                  // Labels include the airport name and runways
+const MAX_AIRPORT_LABELS: usize = 50;
 
 pub struct FlightRadar {
     window: RefCell<PistonWindow>,
     source_provider: SourceProvider,
+    state_source: Option<Box<dyn StateVectorSource>>,
     text_manager: RefCell<text::TextManager>,
 
-    data: AircraftData,
+    aircraft: AircraftStore,
+    trails: TrailStore,
+    alerts: AlertMonitor,
+    database: AircraftDatabase,             // Registration and type details, by icao24
+    airlines: AirlineTable,
+    airports: AirportDatabase,
+    display_clock: SimulationClock,         // Time to which aircraft positions are extrapolated for display
+    max_extrapolation: Duration,
+    backbuffer_updated: Instant,
     flight_data: FlightData,
+    board_airport: Option<String>,          // Airport whose arrivals and departures are shown, if any
+    airport_board: Option<AirportBoard>,
+    airport_board_failed: bool,             // Most recent retrieval of the board failed
+    selected_track: Option<Track>,
     geo_data: geography::GeoData,
 
     draw_size: [u32; 2],
@@ -49,10 +100,17 @@ pub struct FlightRadar {
     cursor_pos: [f64; 2],
 
     mouse_down_point: [Option<[f64; 2]>; MOUSE_BUTTON_COUNT],
-    selected_object: Option<Aircraft>,
-
-    tx_flight_data_req: Option<Sender<(String, Source)>>,       // (Request channel for new flight data)
-    rx_flight_data_resp: Option<Receiver<FlightData>>,          // (Response channel with new flight data)
+    selected: Option<String>,               // icao24 of the selected aircraft
+    search: AircraftSearch,                 // Filter applied to all aircraft shown on the map
+    search_editing: bool,                   // Keyboard input is directed to the search query
+    show_flight_history: bool,
+    units: UnitSystem,
+    simulation_status: SimulationStatus,
+    playback_speed: f64,
+
+    tx_simulate: Option<Sender<SimulationTrigger>>,             // (Trigger channel for new state vector data)
+    tx_flight_data_req: Option<Sender<FlightDataRequest>>,      // (Request channel for new flight data)
+    rx_flight_data_resp: Option<Receiver<FlightDataResponse>>,  // (Response channel with new flight data)
 }
 
 impl FlightRadar {
@@ -60,12 +118,16 @@ impl FlightRadar {
         // Inter-thread channels
         let (tx_data, rx_data) = mpsc::channel();   // (Simulation -> Event loop)
         let (tx_simulate, rx_simulate) = mpsc::channel();   // (Event loop -> trigger simulation)
+        let (tx_status, rx_status) = mpsc::channel();       // (Simulation -> Event loop, playback and source status)
+        let (tx_trigger_control, rx_trigger_control) = mpsc::channel();    // (Simulation -> periodic trigger)
         let tx_periodic_simulation = tx_simulate.clone();
+        self.tx_simulate = Some(tx_simulate);
 
         // Simulation thread and periodic trigger
-        let periodic_source = self.source_provider.source_state_vectors();
-        thread::spawn(move || simulation::simulate(rx_simulate, tx_data));
-        thread::spawn(move || simulation::periodic_trigger(tx_periodic_simulation, periodic_source, 2));
+        let state_source = self.state_source.take().unwrap_or_else(|| panic!("No state vector source available"));
+        let playback_speed = self.playback_speed;
+        thread::spawn(move || simulation::simulate(state_source, playback_speed, rx_simulate, tx_trigger_control, tx_data, tx_status));
+        thread::spawn(move || simulation::periodic_trigger(tx_periodic_simulation, rx_trigger_control, 2));
 
         // Async requests for flight data
         let (tx_flight_data_req, rx_flight_data_req) = mpsc::channel();     // (Request for flight data)
@@ -73,7 +135,8 @@ impl FlightRadar {
 
         self.tx_flight_data_req = Some(tx_flight_data_req);
         self.rx_flight_data_resp = Some(rx_flight_data_resp);
-        thread::spawn(move || simulation::retrieve_flight_data(rx_flight_data_req, tx_flight_data_resp));
+        let provider = self.source_provider.clone();
+        thread::spawn(move || simulation::retrieve_flight_data(provider, rx_flight_data_req, tx_flight_data_resp));
 
         let factory: GfxFactory = self.window().factory.clone();
         let mut texture_context = TextureContext { factory, encoder: self.window_mut().factory.create_command_buffer().into() };
@@ -94,7 +157,7 @@ impl FlightRadar {
                         texture_context = TextureContext { factory, encoder: self.window_mut().factory.create_command_buffer().into() };
                         texture = Texture::from_image(&mut texture_context,&self.canvas, &TextureSettings::new()).unwrap();
 
-                        self.trigger_simulation(&tx_simulate);
+                        self.trigger_simulation();
                     },
                     Input::Button(args) => {
                         match args.button {
@@ -107,6 +170,11 @@ impl FlightRadar {
                             _ => ()
                         }
                     },
+                    Input::Text(text) if self.search_editing => {
+                        self.search.push(&text);
+                        self.update_search();
+                        self.update_backbuffer();
+                    },
                     Input::Move(args) => {
                         match args {
                             Motion::MouseCursor(cursor) => self.mouse_move(&cursor),
@@ -114,6 +182,7 @@ impl FlightRadar {
                             Motion::MouseScroll(scroll) => {
                                 self.perform_zoom(scroll);
                                 self.update_backbuffer();
+                                self.trigger_simulation();
                             },
                             _ => ()
                         }
@@ -138,6 +207,29 @@ impl FlightRadar {
 
                             // Render all window content
                             rendering::perform_rendering(g, &context, scaled_size, zoom_level, view_origin, &self.geo_data);
+                            rendering::render_airports(g, &context, &render_size, zoom_level, &view_origin, &self.airports);
+                            self.render_airport_labels(glyph_cache, &context, g);
+
+                            // Route of the selected flight, between its departure and arrival airports
+                            if let Some(flight) = self.selected_flight() {
+                                let (departure, arrival) = self.route_airports(flight);
+                                if let (Some(departure), Some(arrival)) = (departure, arrival) {
+                                    let current = self.selected_object()
+                                        .and_then(|x| self.display_position(x));
+                                    rendering::render_route(g, &context, zoom_level, &view_origin,
+                                                            (departure.longitude, departure.latitude), (arrival.longitude, arrival.latitude), current);
+                                }
+
+                                let route = departure.into_iter().chain(arrival).collect::<Vec<&Airport>>();
+                                rendering::render_route_airports(g, &context, &render_size, zoom_level, &view_origin, &route);
+                            }
+
+                            // Track of the selected object, behind all aircraft
+                            if let Some(track) = &self.selected_track {
+                                let current = self.selected_object()
+                                    .and_then(|x| self.display_position(x));
+                                rendering::render_track(g, &context, zoom_level, &view_origin, track, current);
+                            }
 
                             // Apply pre-rendered backbuffer target (if not panning the map)
                             if !self.is_mouse_dragging(MOUSE_RIGHT) {
@@ -151,19 +243,37 @@ impl FlightRadar {
                                 rectangle(rendering::colour::COLOUR_SELECTION, rect, context.transform, g);
                             }
 
+                            self.render_alerts(glyph_cache, &context, g);
+                            self.render_search(glyph_cache, &context, g);
                             self.render_selected_object_data(glyph_cache, &context, g);
+                            self.render_flight_history(glyph_cache, &context, g);
+                            self.render_airport_board(glyph_cache, &context, g);
+                            self.render_simulation_status(glyph_cache, &context, g);
 
                             glyph_cache.factory.encoder.flush(device);
                         });
                     },
                     Loop::AfterRender(_ar) => {
-                        if let Ok(d) = rx_data.try_recv() {
-                            self.data = d;
+                        if let Some(status) = rx_status.try_iter().last() {
+                            self.update_display_clock(&status);
+                            self.simulation_status = status;
+                            self.sync_airport_board();
+                        }
+                        if let Ok((d, bounds)) = rx_data.try_recv() {
+                            let events = self.aircraft.apply(d, bounds.as_ref());
+                            self.process_aircraft_events(&events);
+                            self.update_search();
+                            self.display_clock.seek(self.aircraft.time() as f64);
+                            self.sync_airport_board();
+
+                            let rendered = self.update_backbuffer();
+                            println!("Processed: {}, Rendered: {}", self.aircraft.len(), rendered);
+                        }
+                        else if self.is_extrapolating() && self.backbuffer_updated.elapsed() >= BACKBUFFER_REFRESH_INTERVAL {
                             self.update_backbuffer();
                         }
 
                         self.receive_flight_data();
-                        self.update_selection()
                     },
                     _ => ()
                 },
@@ -175,14 +285,75 @@ impl FlightRadar {
     fn key_down(&mut self, _key: &Key) { }
 
     fn key_up(&mut self, key: &Key) {
+        if self.search_editing {
+            self.search_key_up(key);
+            return;
+        }
+
         match key {
-            Key::Home => self.reset_view(),
+            Key::Home => {
+                self.reset_view();
+                self.update_backbuffer();
+                self.trigger_simulation();
+            },
             Key::F12 => rendering::screenshot::display_screenshot(),
+            Key::U => self.units = self.units.next(),
+            Key::H => self.show_flight_history = !self.show_flight_history,
+            Key::B => self.toggle_airport_board(),
+            Key::Slash => self.search_editing = true,
+            Key::Delete => self.clear_search(),
+
+            Key::Space => self.playback_command(PlaybackCommand::TogglePause),
+            Key::D1 => self.playback_command(PlaybackCommand::SetSpeed(PLAYBACK_SPEEDS[0])),
+            Key::D2 => self.playback_command(PlaybackCommand::SetSpeed(PLAYBACK_SPEEDS[1])),
+            Key::D3 => self.playback_command(PlaybackCommand::SetSpeed(PLAYBACK_SPEEDS[2])),
+            Key::D4 => self.playback_command(PlaybackCommand::SetSpeed(PLAYBACK_SPEEDS[3])),
+            Key::Left => self.playback_command(PlaybackCommand::Step(-1)),
+            Key::Right => self.playback_command(PlaybackCommand::Step(1)),
 
             _ => ()
         }
     }
 
+    // Text is entered via text input events; only editing and control keys are handled here
+    fn search_key_up(&mut self, key: &Key) {
+        match key {
+            Key::Backspace => {
+                self.search.pop();
+                self.update_search();
+                self.update_backbuffer();
+            },
+            Key::Return => {
+                self.search_editing = false;
+                let first = self.search_results().first().map(|x| x.icao24.clone());
+                if self.search.is_active() && first.is_some() {
+                    self.select_object(first);
+                }
+            },
+            Key::Delete => self.clear_search(),
+
+            _ => ()
+        }
+    }
+
+    fn clear_search(&mut self) {
+        self.search.clear();
+        self.search_editing = false;
+        self.update_search();
+        self.update_backbuffer();
+    }
+
+    // Results are only recomputed when the query or the aircraft change, rather than on every frame
+    fn update_search(&mut self) {
+        self.search.update(self.aircraft.iter(), &self.database, &self.airlines);
+    }
+
+    fn search_results(&self) -> Vec<&Aircraft> {
+        self.search.results().iter()
+            .filter_map(|x| self.aircraft.get(x))
+            .collect()
+    }
+
     fn mouse_down(&mut self, button: &MouseButton) {
         if let Some(ix) = FlightRadar::mouse_button_index(button) {
             self.mouse_down_point[ix] = Some(self.cursor_pos.clone());
@@ -203,7 +374,10 @@ impl FlightRadar {
 
     fn mouse_click(&mut self, button_index: usize, location: &[f64; 2]) {
         match button_index {
-            MOUSE_LEFT => self.map_click(location),
+            MOUSE_LEFT => match self.timeline_position(location) {
+                Some(time) => self.playback_command(PlaybackCommand::Seek(time)),
+                None => self.map_click(location)
+            },
             _ => ()
         }
     }
@@ -214,9 +388,11 @@ impl FlightRadar {
                 let rect = self.get_drag_selection(MOUSE_LEFT, &self.window_size).unwrap_or_else(|| panic!("No drag data"));
                 self.zoom_to(&rect);
                 self.update_backbuffer();
+                self.trigger_simulation();
             }
             MOUSE_RIGHT => {        // Post-drag
                 self.update_backbuffer();
+                self.trigger_simulation();
             },
             _ => ()
         }
@@ -284,47 +460,49 @@ impl FlightRadar {
 
         // Get the closest object to this click location
         let (origin, zoom) = (self.view_origin, self.zoom_level);
-        let closest = self.data.data
+        let closest = self.aircraft
             .iter()
-            .enumerate()
-            .filter(|(_, x)| x.longitude.is_some() && x.latitude.is_some())
-            .map(|(i, x)| (i, lon_lat_to_map(x.longitude.unwrap(), x.latitude.unwrap(), &origin, zoom)))
-            .map(|(i, pos)| (i, ((pos.0 - loc.0).abs(), (pos.1 - loc.1).abs())))
-            .map(|(i, dxy)| (i, dxy.0 * dxy.0 + dxy.1 * dxy.1))  // Squared distance to point
+            .filter(|x| self.search.includes(&x.icao24))
+            .filter_map(|x| self.display_position(x).map(|(lon, lat)| (&x.icao24, lon_lat_to_map(lon, lat, &origin, zoom))))
+            .map(|(id, pos)| (id, ((pos.0 - loc.0).abs(), (pos.1 - loc.1).abs())))
+            .map(|(id, dxy)| (id, dxy.0 * dxy.0 + dxy.1 * dxy.1))  // Squared distance to point
             .filter(|(_, d2)| *d2 <= MAX_OBJECT_SELECT_DISTANCE_SQ)
-            .fold(None, |closest: Option<(usize, f64)>, (i, d2)|
-                if closest.is_none() || d2 < closest.unwrap().1 {Some((i, d2))} else {closest});
+            .fold(None, |closest: Option<(&String, f64)>, (id, d2)|
+                if closest.is_none() || d2 < closest.unwrap().1 {Some((id, d2))} else {closest});
 
-        self.select_object(closest.map(|(index, _)| index));
+        self.select_object(closest.map(|(icao24, _)| icao24.clone()));
     }
 
-    fn select_object(&mut self, index: Option<usize>) {
+    fn select_object(&mut self, icao24: Option<String>) {
         // Select the new object
-        self.selected_object = index
-            .map(|i| self.data.data[i].clone());
+        self.selected = icao24;
+        self.selected_track = None;
+        self.flight_data.clear();
 
-        // Issue a request for detailed flight data
-        let icao24 = self.selected_object.as_ref()
-            .map(|x| x.icao24.clone())
-            .unwrap_or("[None]".to_string());
+        // Issue requests for detailed flight data and the recent track of this object
         let timestamp = get_current_timestamp_secs();
 
-        if self.tx_flight_data_req.is_some() {
+        if let (Some(tx), Some(icao24)) = (&self.tx_flight_data_req, &self.selected) {
             println!("Issuing request for \"{}\" flight details", icao24);
-            self.tx_flight_data_req.as_ref().unwrap().send((
+            tx.send(FlightDataRequest::Flights(
                 icao24.clone(),
                 self.source_provider.source_flight_data(
-                    &icao24,
-                    timestamp - (2 * 24 * 60 * 60),   // -5d
+                    icao24,
+                    timestamp - (2 * 24 * 60 * 60),   // -2d
                     timestamp
                 )
             )).unwrap_or_else(|e| println!("Failed to issue flight details request ({:?})", e));
+
+            tx.send(FlightDataRequest::Track(
+                icao24.clone(),
+                self.source_provider.source_track(icao24, 0)
+            )).unwrap_or_else(|e| println!("Failed to issue track request ({:?})", e));
         }
     }
 
     fn render_selected_object_data(&self, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
-        if let Some(obj) = &self.selected_object {
-            if let (Some(lon), Some(lat)) = (obj.longitude, obj.latitude) {
+        if let Some(obj) = self.selected_object() {
+            if let Some((lon, lat)) = self.display_position(obj) {
                 let (x, y) = lon_lat_to_map(lon, lat, &self.view_origin, self.zoom_level);
 
                 let adj = normalise_to_window(SELECTION_CIRCLE_RADIUS, SELECTION_CIRCLE_RADIUS, &self.draw_sizef);
@@ -338,21 +516,333 @@ impl FlightRadar {
                                 [select_min.0, select_min.1],
                                 [select_max.0, select_max.1], context.transform, g);
 
-                // Status area
-                rectangle(COLOUR_STATUS_AREA_BACK, [0.0, 1.0 - STATUS_AREA_SIZE, 1.0, STATUS_AREA_SIZE], context.transform, g);
-                line_from_to(COLOUR_STATUS_AREA_OUTLINE, 0.001, [0.0, 1.0 - STATUS_AREA_SIZE], [1.0, 1.0 - STATUS_AREA_SIZE], context.transform, g);
+                self.render_status_area(context, g);
 
-                // Object information
+                // Object information, highlighted if the object has raised an alert
+                let text_colour = if self.alerts.is_alerting(&obj.icao24) { COLOUR_ALERT } else { COLOUR_STATUS_AREA_TEXT };
                 // {"time":1566137050,"states":[["ac96b8","AAL137  ","United States",1566136785,1566136790,-97.0546,32.9235,228.6,false,72.02,180,-4.88,null,213.36,"0755",false,0]
+                let details = vec![
+                    self.airline_flight(obj),
+                    self.database.get(&obj.icao24).map(|x| x.summary()).filter(|x| !x.is_empty())
+                ].into_iter().flatten().collect::<Vec<String>>().join("   ");
+
+                let route = self.selected_flight()
+                    .map(|x| vec![Some(self.route_description(x)), self.route_progress(obj, x)])
+                    .unwrap_or_default()
+                    .into_iter().flatten().collect::<Vec<String>>().join("   ");
+
                 self.render_text_lines(vec![
-                    obj.basic_status().as_str()
+                    obj.basic_status(self.units).as_str(),
+                    details.as_str(),
+                    route.as_str()
                 ],
-                &[0.01, 1.0 - STATUS_AREA_SIZE + 0.02], 16.0, COLOUR_STATUS_AREA_TEXT, 14, glyph_cache, context, g
+                &[0.01, 1.0 - STATUS_AREA_SIZE + 0.02], 16.0, text_colour, 14, glyph_cache, context, g
                 );
             }
         }
     }
 
+    // Highlights every aircraft with an active alert, and lists the alerts in a panel at the top-right of the view
+    fn render_alerts(&self, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
+        let alerts = self.alerts.active();
+        if alerts.is_empty() {
+            return;
+        }
+
+        let adj = normalise_to_window(ALERT_CIRCLE_RADIUS, ALERT_CIRCLE_RADIUS, &self.draw_sizef);
+        let highlight = Ellipse::new_border(COLOUR_ALERT, adj.0 * 0.2);
+
+        alerts.iter()
+            .filter_map(|x| self.aircraft.get(&x.icao24))
+            .filter_map(|x| self.display_position(x))
+            .map(|(lon, lat)| lon_lat_to_map(lon, lat, &self.view_origin, self.zoom_level))
+            .for_each(|(x, y)| highlight.draw_from_to([x - adj.0, y - adj.1], [x + adj.0, y + adj.1],
+                                                      &context.draw_state, context.transform, g));
+
+        let mut lines = vec![format!("ALERTS ({})", alerts.len())];
+        lines.extend(alerts.iter().take(MAX_ALERT_PANEL_ENTRIES).map(|x| x.summary()));
+        if alerts.len() > MAX_ALERT_PANEL_ENTRIES {
+            lines.push(format!("+{} more", alerts.len() - MAX_ALERT_PANEL_ENTRIES));
+        }
+
+        let [x, y, width, line_spacing] = ALERT_PANEL;
+        rectangle(COLOUR_ALERT_PANEL_BACK, [x - 0.01, y - line_spacing, width, line_spacing * (lines.len() as f64 + 0.5)], context.transform, g);
+        self.render_text_lines(lines.iter().map(|x| x.as_str()).collect(), &[x, y], line_spacing, COLOUR_ALERT, 12, glyph_cache, context, g);
+    }
+
+    // Current search query and the first few matching aircraft, at the top-left of the view
+    fn render_search(&self, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
+        if !self.search_editing && !self.search.is_active() {
+            return;
+        }
+
+        let results = self.search_results();
+        let mut lines = vec![format!("SEARCH: {}{}  ({} found)", self.search.query(), if self.search_editing { "_" } else { "" }, results.len())];
+        lines.extend(results.iter().take(MAX_SEARCH_PANEL_ENTRIES).map(|x| format!("{} ({})  {}",
+            self.airline_flight(x).or_else(|| x.callsign.as_deref().map(str::trim).filter(|x| !x.is_empty()).map(str::to_string))
+                .unwrap_or("[Unknown callsign]".to_string()),
+            x.icao24,
+            self.database.get(&x.icao24).map(|info| info.summary()).unwrap_or_default())));
+        if results.len() > MAX_SEARCH_PANEL_ENTRIES {
+            lines.push(format!("+{} more", results.len() - MAX_SEARCH_PANEL_ENTRIES));
+        }
+
+        let [x, y, width, line_spacing] = SEARCH_PANEL;
+        rectangle(COLOUR_SEARCH_PANEL_BACK, [x - 0.005, y - line_spacing, width, line_spacing * (lines.len() as f64 + 0.5)], context.transform, g);
+        self.render_text_lines(lines.iter().map(|x| x.as_str()).collect(), &[x, y], line_spacing, COLOUR_STATUS_AREA_TEXT, 12, glyph_cache, context, g);
+    }
+
+    // Identifies the larger airports in view, once zoomed in far enough for labels not to obscure the map
+    fn render_airport_labels(&self, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
+        if self.zoom_level < AIRPORT_LABEL_MIN_ZOOM {
+            return;
+        }
+
+        let offset = normalise_to_window(4.0, -4.0, &self.draw_sizef);
+        self.airports.visible_at(self.zoom_level)
+            .map(|x| (x, lon_lat_to_map(x.longitude, x.latitude, &self.view_origin, self.zoom_level)))
+            .filter(|(_, pos)| in_bounds(*pos))
+            .take(MAX_AIRPORT_LABELS)
+            .for_each(|(airport, (x, y))| {
+                let label = if self.zoom_level >= AIRPORT_DETAIL_MIN_ZOOM { airport.detailed_summary() } else { airport.ident.clone() };
+                self.render_text(label.as_str(), &[x + offset.0, y + offset.1], COLOUR_AIRPORT_LABEL, 10, glyph_cache, context, g);
+            });
+    }
+
+    // Recent flights of the selected aircraft, most recent first, if flight data has been received for it
+    fn selected_flights(&self) -> Vec<&Flight> {
+        self.selected.as_ref()
+            .map(|x| flights::recent_flights(&self.flight_data, x))
+            .unwrap_or_default()
+    }
+
+    fn selected_flight(&self) -> Option<&Flight> {
+        self.selected_flights().first().copied()
+    }
+
+    // Estimated departure and arrival airports of the flight, where known
+    fn route_airports(&self, flight: &Flight) -> (Option<&Airport>, Option<&Airport>) {
+        (flight.departure().and_then(|x| self.airports.get(x)), flight.arrival().and_then(|x| self.airports.get(x)))
+    }
+
+    // e.g. "EGLL London Heathrow Airport → KJFK John F Kennedy International Airport"
+    fn route_description(&self, flight: &Flight) -> String {
+        format!("{} \u{2192} {}", self.describe_airport(flight.departure()), self.describe_airport(flight.arrival()))
+    }
+
+    // e.g. "EGLL London Heathrow Airport", or the bare code of any airport not in the database
+    fn describe_airport(&self, code: Option<&str>) -> String {
+        code.map(|x| self.airports.get(x).map(|airport| airport.summary()).unwrap_or_else(|| x.to_string()))
+            .unwrap_or("[Unknown]".to_string())
+    }
+
+    // Opens the board of the airport under the cursor, or closes the current board if there is no other
+    // airport under the cursor
+    fn toggle_airport_board(&mut self) {
+        let airport = self.airport_at_cursor()
+            .map(|x| x.ident.clone())
+            .filter(|x| self.board_airport.as_ref() != Some(x));
+
+        if airport.is_none() && self.board_airport.is_none() {
+            println!("No airport under the cursor");
+            return;
+        }
+
+        self.board_airport = airport;
+        self.airport_board = None;
+        self.airport_board_failed = false;
+        self.sync_airport_board();
+    }
+
+    // Informs the flight data worker of the current board airport, and of the simulation clock which determines
+    // the window of flights listed on it
+    fn sync_airport_board(&self) {
+        if let Some(tx) = &self.tx_flight_data_req {
+            tx.send(FlightDataRequest::AirportBoard(self.board_airport.clone(), self.display_clock.clone()))
+                .unwrap_or_else(|e| println!("Failed to issue airport board request ({:?})", e));
+        }
+    }
+
+    // Closest airport shown on the map to the cursor, if any is within selection distance
+    fn airport_at_cursor(&self) -> Option<&Airport> {
+        let cursor = normalised_coords(&self.cursor_pos, &self.window_size);
+        let radius = normalise_to_window(AIRPORT_SELECT_RADIUS, AIRPORT_SELECT_RADIUS, &self.window_size);
+
+        self.airports.visible_at(self.zoom_level)
+            .map(|x| (x, lon_lat_to_map(x.longitude, x.latitude, &self.view_origin, self.zoom_level)))
+            .filter(|(_, pos)| in_bounds(*pos))
+            .map(|(x, pos)| (x, ((pos.0 - cursor.0) / radius.0).powi(2) + ((pos.1 - cursor.1) / radius.1).powi(2)))
+            .filter(|(_, d2)| *d2 <= 1.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(x, _)| x)
+    }
+
+    // Recent arrivals and departures at the chosen airport, in a panel at the left of the view
+    fn render_airport_board(&self, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
+        let airport = match &self.board_airport {
+            Some(x) => x,
+            None => return
+        };
+
+        let mut lines = vec![self.describe_airport(Some(airport))];
+        match &self.airport_board {
+            Some(board) => {
+                lines[0] += &format!("   (as of {}{})", utc_datetime_from_timestamp(board.time).format("%H:%M UTC"),
+                                     if self.airport_board_failed { ", refresh failed" } else { "" });
+                for movement in [Movement::Arrival, Movement::Departure].iter() {
+                    let entries = board.entries(*movement);
+                    let title = if *movement == Movement::Arrival { "ARRIVALS" } else { "DEPARTURES" };
+
+                    lines.push(format!("{} ({})", title, entries.len()));
+                    lines.extend(entries.iter().take(MAX_BOARD_PANEL_ENTRIES).map(|x| self.board_entry_summary(x)));
+                }
+            },
+            None if self.airport_board_failed => lines.push("Failed to retrieve arrivals and departures".to_string()),
+            None => lines.push("Retrieving arrivals and departures...".to_string())
+        }
+
+        let [x, y, width, line_spacing] = BOARD_PANEL;
+        rectangle(COLOUR_BOARD_PANEL_BACK, [x - 0.005, y - line_spacing, width, line_spacing * (lines.len() as f64 + 0.5)], context.transform, g);
+        self.render_text_lines(lines.iter().map(|x| x.as_str()).collect(), &[x, y], line_spacing, COLOUR_STATUS_AREA_TEXT, 11, glyph_cache, context, g);
+    }
+
+    // e.g. "Tue 14:05  BAW123  from KJFK John F Kennedy International Airport  [live at 51.470, -0.454, 12nm away]"
+    fn board_entry_summary(&self, entry: &BoardEntry) -> String {
+        let airport = self.board_airport.as_ref().and_then(|x| self.airports.get(x));
+        let live = self.aircraft.get(&entry.flight.icao24)
+            .and_then(|x| self.display_position(x))
+            .map(|(lon, lat)| match airport {
+                Some(airport) => format!("  [live at {:.3}, {:.3}, {} away]", lat, lon,
+                    Distance::from_metres(greatcircle::distance((lon, lat), (airport.longitude, airport.latitude)) as f32).format(self.units)),
+                None => format!("  [live at {:.3}, {:.3}]", lat, lon)
+            })
+            .unwrap_or_default();
+
+        format!("{}  {}  {} {}{}",
+                utc_datetime_from_timestamp(entry.time()).format("%a %H:%M"),
+                entry.flight.callsign().unwrap_or("[Unknown callsign]"),
+                if entry.movement == Movement::Arrival { "from" } else { "to" },
+                self.describe_airport(entry.counterpart()),
+                live)
+    }
+
+    // Distance remaining to the arrival airport and, if the aircraft is under way, its estimated time of arrival
+    // at the current ground speed, e.g. "2991nm to go, ETA 14:32 UTC (6h 05m)"
+    fn route_progress(&self, aircraft: &Aircraft, flight: &Flight) -> Option<String> {
+        let arrival = self.route_airports(flight).1?;
+        let position = self.display_position(aircraft)?;
+
+        let remaining = greatcircle::distance(position, (arrival.longitude, arrival.latitude));
+        let distance = Distance::from_metres(remaining as f32).format(self.units);
+
+        match aircraft.velocity.filter(|x| !aircraft.on_ground && x.metres_per_sec() >= MIN_ETA_SPEED) {
+            Some(speed) => {
+                let time_remaining = (remaining / speed.metres_per_sec() as f64) as i64;
+                let eta = utc_datetime_from_timestamp(self.display_clock.now() as i64 + time_remaining);
+
+                Some(format!("{} to go, ETA {} ({}h {:02}m)", distance, eta.format("%H:%M UTC"),
+                             time_remaining / 3600, (time_remaining % 3600) / 60))
+            },
+            None => Some(format!("{} to go", distance))
+        }
+    }
+
+    // Recent flights of the selected aircraft, above the status area at the bottom-right of the view
+    fn render_flight_history(&self, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
+        let flights = self.selected_flights();
+        if !self.show_flight_history || flights.is_empty() {
+            return;
+        }
+
+        let time = |t: i64| utc_datetime_from_timestamp(t).format("%Y-%m-%d %H:%M").to_string();
+        let mut lines = vec![format!("RECENT FLIGHTS ({})", flights.len())];
+        lines.extend(flights.iter().take(MAX_FLIGHT_PANEL_ENTRIES).map(|x| format!("{} - {}  {}  {}  [candidates {}/{}]",
+            time(x.first_seen), time(x.last_seen),
+            x.callsign().unwrap_or("[Unknown callsign]"),
+            self.route_description(x),
+            x.departure_airport_candidates_count, x.arrival_airport_candidates_count)));
+        if flights.len() > MAX_FLIGHT_PANEL_ENTRIES {
+            lines.push(format!("+{} more", flights.len() - MAX_FLIGHT_PANEL_ENTRIES));
+        }
+
+        let [x, width, line_spacing] = FLIGHT_PANEL;
+        let y = 1.0 - STATUS_AREA_SIZE - line_spacing * (lines.len() as f64 - 0.5);
+        rectangle(COLOUR_FLIGHT_PANEL_BACK, [x - 0.005, y - line_spacing, width, line_spacing * (lines.len() as f64 + 0.5)], context.transform, g);
+        self.render_text_lines(lines.iter().map(|x| x.as_str()).collect(), &[x, y], line_spacing, COLOUR_STATUS_AREA_TEXT, 11, glyph_cache, context, g);
+    }
+
+    // Callsign resolved to its airline, e.g. "AAL137 → American Airlines AA137", if a known airline flight
+    fn airline_flight(&self, aircraft: &Aircraft) -> Option<String> {
+        aircraft.callsign.as_deref()
+            .and_then(|x| self.airlines.resolve(x))
+            .map(|x| x.to_string())
+    }
+
+    fn render_status_area(&self, context: &Context, g: &mut G2d) {
+        rectangle(COLOUR_STATUS_AREA_BACK, [0.0, 1.0 - STATUS_AREA_SIZE, 1.0, STATUS_AREA_SIZE], context.transform, g);
+        line_from_to(COLOUR_STATUS_AREA_OUTLINE, 0.001, [0.0, 1.0 - STATUS_AREA_SIZE], [1.0, 1.0 - STATUS_AREA_SIZE], context.transform, g);
+    }
+
+    // Current data time and playback state, plus a timeline bar when replaying a recorded session, and any
+    // problems with the source.  Nothing is shown for a live feed running normally
+    fn render_simulation_status(&self, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
+        let status = &self.simulation_status;
+        let show_playback = status.timeline.is_some() || status.paused;
+        let source_warning = status.source_error.clone().or_else(|| status.requests_remaining
+            .filter(|&x| x < simulation::LOW_RATE_LIMIT_REMAINING)
+            .map(|x| format!("{} requests remaining", x)));
+
+        if !show_playback && source_warning.is_none() {
+            return;
+        }
+
+        if !self.has_selected_position() {
+            self.render_status_area(context, g);
+        }
+
+        if let Some(warning) = source_warning {
+            self.render_text(warning.as_str(), &[0.55, TIMELINE_BAR[1] - 0.01], COLOUR_STATUS_AREA_WARNING, 12, glyph_cache, context, g);
+        }
+
+        if !show_playback {
+            return;
+        }
+
+        let state = match (&status.timeline, status.paused) {
+            (_, true) => "PAUSED".to_string(),
+            (None, false) => "LIVE".to_string(),
+            (Some(_), false) => format!("{}x", status.speed)
+        };
+        let time = utc_datetime_from_timestamp(self.aircraft.time() as i64).format("%Y-%m-%d %H:%M:%S UTC");
+
+        self.render_text(format!("{}  [{}]", time, state).as_str(),
+                         &[TIMELINE_BAR[0], TIMELINE_BAR[1] - 0.01], COLOUR_STATUS_AREA_TEXT, 12, glyph_cache, context, g);
+
+        if let Some((start, end)) = status.timeline {
+            let progress = if end > start { (self.aircraft.time() - start) as f64 / (end - start) as f64 } else { 1.0 };
+
+            rectangle(COLOUR_TIMELINE_BACK, TIMELINE_BAR, context.transform, g);
+            rectangle(COLOUR_TIMELINE_PROGRESS, [TIMELINE_BAR[0], TIMELINE_BAR[1], TIMELINE_BAR[2] * progress.clamp(0.0, 1.0), TIMELINE_BAR[3]],
+                      context.transform, g);
+        }
+    }
+
+    fn has_selected_position(&self) -> bool {
+        self.selected_object()
+            .map(|x| x.longitude.is_some() && x.latitude.is_some())
+            .unwrap_or(false)
+    }
+
+    // Time corresponding to the given window location, if it falls on the timeline bar
+    fn timeline_position(&self, location: &[f64; 2]) -> Option<isize> {
+        let (x, y) = normalised_coords(location, &self.window_size);
+
+        self.simulation_status.timeline
+            .filter(|_| (TIMELINE_BAR[0]..=TIMELINE_BAR[0] + TIMELINE_BAR[2]).contains(&x))
+            .filter(|_| (TIMELINE_BAR[1] - TIMELINE_CLICK_MARGIN..=TIMELINE_BAR[1] + TIMELINE_BAR[3] + TIMELINE_CLICK_MARGIN).contains(&y))
+            .map(|(start, end)| start + (((x - TIMELINE_BAR[0]) / TIMELINE_BAR[2]) * (end - start) as f64) as isize)
+    }
+
     fn render_text(&self, text: &str, pos: &[f64; 2], colour: [f32; 4], font_size: u32, glyph_cache: &mut Glyphs, context: &Context, g: &mut G2d) {
         piston_window::text::Text::new_color(colour, font_size).draw(
             text,
@@ -381,10 +871,21 @@ impl FlightRadar {
         self.canvas = image::ImageBuffer::new(self.draw_size[0], self.draw_size[1]);
     }
 
-    fn trigger_simulation(&self, tx: &Sender<Source>) {
-        let source = self.source_provider.source_state_vectors();
-        if let Some(err) = tx.send(source).err() {
-            eprintln!("Failed to trigger simulation cycle: {}", err.to_string());
+    // Request new data for the current view; bounded to the visible area when zoomed in
+    fn trigger_simulation(&self) {
+        let bounds = GeoBounds::from_view(&self.view_origin, self.zoom_level);
+        if let Some(tx) = &self.tx_simulate {
+            if let Some(err) = tx.send(SimulationTrigger::UpdateView(bounds)).err() {
+                eprintln!("Failed to trigger simulation cycle: {}", err);
+            }
+        }
+    }
+
+    fn playback_command(&self, command: PlaybackCommand) {
+        if let Some(tx) = &self.tx_simulate {
+            if let Some(err) = tx.send(SimulationTrigger::Playback(command)).err() {
+                eprintln!("Failed to send playback command: {}", err);
+            }
         }
     }
 
@@ -392,28 +893,103 @@ impl FlightRadar {
         if self.rx_flight_data_resp.is_some() {
             let rcv = self.rx_flight_data_resp.as_ref().unwrap().try_recv();
             match rcv {
-                Ok(x) => {
+                Ok(FlightDataResponse::Flights(x)) => {
                     println!("Received {} flight data entries: {:?}", x.len(), x);
                     self.flight_data = x;
                 }
+                Ok(FlightDataResponse::AirportBoard(airport, board)) if self.board_airport.as_ref() == Some(&airport) => {
+                    // Any previous board is retained if a refresh fails
+                    self.airport_board_failed = board.is_none();
+                    match board {
+                        Some(x) => {
+                            println!("Received {} arrivals and {} departures for \"{}\"", x.arrivals.len(), x.departures.len(), x.airport);
+                            self.airport_board = Some(x);
+                        },
+                        None => println!("Failed to retrieve board for \"{}\"", airport)
+                    }
+                }
+                // Discard any track which arrives after the selection has moved on
+                Ok(FlightDataResponse::Track(x)) if self.is_selected(&x.icao24) => {
+                    println!("Received track with {} waypoints for \"{}\"", x.path.len(), x.icao24);
+                    self.selected_track = Some(x);
+                }
                 _ => ()     // Allowed, this is a non-blocking try-read
             }
         }
     }
 
-    fn update_selection(&mut self) {
-        if self.selected_object.is_some() {
-            let new = self.selected_object.as_ref()
-                .and_then(|x| self.data
-                    .linear_search(|&y| x.icao24 == y.icao24)
-                    .map(|x| x.clone()));
+    fn is_selected(&self, icao24: &str) -> bool {
+        self.selected.as_deref() == Some(icao24)
+    }
+
+    fn selected_object(&self) -> Option<&Aircraft> {
+        self.selected.as_ref().and_then(|x| self.aircraft.get(x))
+    }
 
-            self.selected_object = new;
+    // Reacts to changes in the aircraft data, rather than rescanning every aircraft
+    fn process_aircraft_events(&mut self, events: &[AircraftEvent]) {
+        self.alerts.process(events, &self.aircraft, self.units);
+
+        for event in events {
+            match event {
+                AircraftEvent::Added(icao24) | AircraftEvent::Updated(icao24) => {
+                    if let Some(aircraft) = self.aircraft.get(icao24) {
+                        self.trails.record(aircraft);
+                    }
+                },
+                // Only aircraft which have disappeared or become stale are lost, so trails survive changes of view
+                AircraftEvent::Lost(icao24) => self.trails.remove(icao24)
+            }
         }
+
+        let selection_lost = events.iter()
+            .any(|x| matches!(x, AircraftEvent::Lost(icao24) if self.is_selected(icao24)));
+
+        if selection_lost {
+            self.selected = None;
+            self.selected_track = None;
+        }
+    }
+
+    // Redraws all aircraft matching the current search, returning the number rendered
+    fn update_backbuffer(&mut self) -> usize {
+        let extrapolation = self.extrapolation();
+        self.backbuffer_updated = Instant::now();
+
+        let aircraft = self.aircraft.iter()
+            .filter(|x| self.search.includes(&x.icao24))
+            .collect::<Vec<&Aircraft>>();
+
+        rendering::prepare_backbuffer(&mut self.canvas, &self.draw_size, self.zoom_level, self.view_origin,
+                                      &aircraft, &self.trails, &extrapolation)
+    }
+
+    fn extrapolation(&self) -> Extrapolation {
+        Extrapolation { time: self.display_clock.now(), max_age: self.max_extrapolation }
+    }
+
+    // Position at which the aircraft is displayed, extrapolated from its last report
+    fn display_position(&self, aircraft: &Aircraft) -> Option<(f64, f64)> {
+        self.extrapolation().position_of(aircraft).map(|x| (x.longitude, x.latitude))
     }
 
-    fn update_backbuffer(&mut self) {
-        rendering::prepare_backbuffer(&mut self.canvas, &self.draw_size, self.zoom_level, self.view_origin, &self.data);
+    // Positions only need to be redrawn between snapshots if they are being extrapolated in time
+    fn is_extrapolating(&self) -> bool {
+        self.max_extrapolation > Duration::from_secs(0) && !self.display_clock.is_paused()
+    }
+
+    // Keeps the display clock in step with the simulation, so that extrapolation follows playback
+    fn update_display_clock(&mut self, status: &SimulationStatus) {
+        if status.paused && !self.display_clock.is_paused() {
+            self.display_clock.pause();
+        }
+        else if !status.paused && self.display_clock.is_paused() {
+            self.display_clock.resume();
+        }
+
+        if status.speed != self.display_clock.speed() {
+            self.display_clock.set_speed(status.speed);
+        }
     }
 
     #[allow(unused_parens)]
@@ -465,11 +1041,33 @@ impl FlightRadar {
         let mut window = FlightRadar::init_window(&options);
         let text_manager = FlightRadar::init_text_manager(text::DEFAULT_FONT.to_string(), &mut window);
 
-        let creds = FlightRadar::init_creds();
-        let source_provider = SourceProvider::new(&creds, options.use_cache);
+        let credentials = FlightRadar::init_credentials();
+        let source_provider = SourceProvider::new(credentials, options.use_cache);
         println!("Connecting to {} sources", if source_provider.is_authenticated() { "authenticated" } else { "unauthenticated" });
 
-        let data = AircraftData::empty();
+        // OpenSky is used unless other sources are specified, and several sources are combined into one
+        let mut state_sources = options.state_sources;
+        if state_sources.is_empty() || options.include_opensky {
+            state_sources.insert(0, Box::new(OpenSkySource::new(source_provider.clone())));
+        }
+        let mut state_source: Box<dyn StateVectorSource> = match state_sources.len() {
+            1 => state_sources.remove(0),
+            _ => Box::new(FusionSource::new(state_sources))
+        };
+        if let Some(path) = &options.record_path {
+            let recorder = SessionRecorder::create(path)
+                .unwrap_or_else(|e| panic!("Cannot create session recording \"{}\" ({})", path, e));
+            state_source = Box::new(RecordingSource::new(state_source, recorder));
+        }
+        println!("Retrieving state vectors from {}", state_source.name());
+
+        let alert_log = options.alert_log.as_deref();
+        let alerts = AlertMonitor::new(alert_log)
+            .unwrap_or_else(|e| panic!("Cannot open alert log \"{}\" ({})", alert_log.unwrap_or_default(), e));
+
+        let database = aircraft_database::load_aircraft_database(options.aircraft_database.as_deref());
+        let airlines = airlines::load_airline_data();
+        let airports = airports::load_airports(options.airports.as_deref(), options.runways.as_deref());
         let geo_data = data::geography::load_coastline_data();
 
         let draw_size: [u32; 2] = [window.draw_size().width as u32, window.draw_size().height as u32];
@@ -480,10 +1078,23 @@ impl FlightRadar {
         Self {
             window: RefCell::new(window),
             source_provider,
+            state_source: Some(state_source),
             text_manager: RefCell::new(text_manager),
 
-            data,
+            aircraft: AircraftStore::new(),
+            trails: TrailStore::new(options.trail_retention),
+            alerts,
+            database,
+            airlines,
+            airports,
+            display_clock: SimulationClock::new(get_current_timestamp_secs() as f64, 1.0),
+            max_extrapolation: options.max_extrapolation,
+            backbuffer_updated: Instant::now(),
             flight_data: FlightData::new(),
+            board_airport: None,
+            airport_board: None,
+            airport_board_failed: false,
+            selected_track: None,
             geo_data,
 
             draw_size,
@@ -496,8 +1107,15 @@ impl FlightRadar {
             cursor_pos: [0.0, 0.0],
 
             mouse_down_point: [None; MOUSE_BUTTON_COUNT],
-            selected_object: None,
-
+            selected: None,
+            search: AircraftSearch::new(),
+            search_editing: false,
+            show_flight_history: true,
+            units: options.units,
+            simulation_status: SimulationStatus::live(),
+            playback_speed: options.playback_speed,
+
+            tx_simulate: None,
             tx_flight_data_req: None,
             rx_flight_data_resp: None
         }
@@ -521,11 +1139,10 @@ impl FlightRadar {
         text::TextManager::create(font, glyph_cache)
     }
 
-    fn init_creds() -> Option<String> {
-        match std::fs::read_to_string("cred") {
-            Ok(x) => Some(x),
-            _ => None
-        }
+    fn init_credentials() -> Option<CredentialProvider> {
+        Credentials::discover()
+            .unwrap_or_else(|e| panic!("Failed to initialise credentials ({})", e))
+            .map(CredentialProvider::new)
     }
 
     fn mouse_button_index(button: &MouseButton) -> Option<usize> {
@@ -543,5 +1160,16 @@ impl FlightRadar {
 
 pub struct BuildOptions {
     pub gl_version: OpenGL,
-    pub use_cache: bool
+    pub use_cache: bool,
+    pub state_sources: Vec<Box<dyn StateVectorSource>>,     // Combined if more than one; defaults to OpenSky if none
+    pub include_opensky: bool,                              // Use OpenSky alongside any other sources
+    pub record_path: Option<String>,                        // Session file to record all snapshots to, if any
+    pub playback_speed: f64,                                // Initial speed when replaying a recorded session
+    pub trail_retention: TrailRetention,                    // Length of the position history shown behind each aircraft
+    pub max_extrapolation: Duration,                        // Furthest positions are extrapolated beyond the last report; zero to disable
+    pub alert_log: Option<String>,                          // File to which all alerts are appended, if any
+    pub units: UnitSystem,                                  // Initial unit system for display
+    pub aircraft_database: Option<String>,                  // Aircraft metadata CSV, if not in the default location
+    pub airports: Option<String>,                           // Airport and runway CSVs, if not in the default locations
+    pub runways: Option<String>
 }
\ No newline at end of file
@@ -1 +1,2 @@
-pub mod flight_radar;
\ No newline at end of file
+pub mod flight_radar;
+pub mod arguments;
@@ -1,9 +1,14 @@
 use serde::*;
 use serde_tuple::*;
-use std::time::SystemTime;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use crate::data::icao_address;
+use crate::geo::greatcircle;
 use crate::util::temporal;
+use crate::util::units::{Altitude, Speed, VerticalRate, UnitSystem};
 
-#[derive(Clone, Debug, Serialize, Deserialize_tuple)]
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
 pub struct Aircraft {
     pub icao24: String,
     pub callsign: Option<String>,           // Can be null if not received
@@ -12,57 +17,299 @@ pub struct Aircraft {
     pub last_contact: i64,                  // Time of last update received, as unix timestamp
     pub longitude: Option<f64>,             // Can be null if not received
     pub latitude: Option<f64>,              // Can be null if not received
-    pub baro_altitude: Option<f32>,         // Barometric altitude, meters.  Can be null
+    pub baro_altitude: Option<Altitude>,    // Barometric altitude.  Can be null
     pub on_ground: bool,
-    pub velocity: Option<f32>,              // Ground speed, m/s.  Can be null if not received
+    pub velocity: Option<Speed>,            // Ground speed.  Can be null if not received
     pub true_track: Option<f32>,            // Decimal degrees clockwise from N.  Can be null
-    pub vertical_rate: Option<f32>,         // m/s, positive means climbing
+    pub vertical_rate: Option<VerticalRate>,
     pub sensors: Option<Vec<i32>>,          // Source sensor; will not contain useful data in these queries
-    pub geo_altitude: Option<f32>,          // Geometric altitude, meters.  Can be null
-    pub squawk: Option<String>,             // Transponder code.  Can be null
+    pub geo_altitude: Option<Altitude>,     // Geometric altitude.  Can be null
+
+    #[serde(deserialize_with = "deserialize_squawk")]
+    pub squawk: Option<Squawk>,             // Transponder code.  Can be null
     pub spi: bool,                          // Special purpose indicator
-    pub position_source: i32                // 0=ADS-B, 1=ASTERIX, 2=MLAT
+    pub position_source: PositionSource,
+
+    #[serde(skip)]
+    pub data_source: Option<String>         // Source which supplied the position, when several are combined
+}
+
+// Origin of the position in a state vector, serialised as the numeric code used by OpenSky
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "i32", into = "i32")]
+pub enum PositionSource {
+    AdsB,                                   // 0
+    Asterix,                                // 1
+    Mlat,                                   // 2
+    Flarm,                                  // 3
+    Unknown(i32)
+}
+
+// Mode A transponder code, of four octal digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Squawk(u16);
+
+// Position of an aircraft estimated at some time after its last report
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimatedPosition {
+    pub longitude: f64,
+    pub latitude: f64,
+    pub altitude: Option<Altitude>
+}
+
+// Time to which aircraft positions are extrapolated for display, and the furthest any may be extrapolated
+#[derive(Debug, Clone, Copy)]
+pub struct Extrapolation {
+    pub time: f64,                          // Unix timestamp
+    pub max_age: Duration
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AircraftData {
     pub time: isize,                        // Time of data receipt
 
-    #[serde(rename = "states")]
+    #[serde(rename = "states", deserialize_with = "deserialize_states")]
     pub data: Vec<Aircraft>                 // All state vectors
 }
 
+// OpenSky reports `"states": null`, rather than an empty array, when no aircraft match the query
+fn deserialize_states<'de, D>(deserializer: D) -> Result<Vec<Aircraft>, D::Error>
+    where D: Deserializer<'de> {
+    Ok(Option::<Vec<Aircraft>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+// Squawk codes which are not valid are treated as missing, rather than failing the whole snapshot
+fn deserialize_squawk<'de, D>(deserializer: D) -> Result<Option<Squawk>, D::Error>
+    where D: Deserializer<'de> {
+    Ok(Option::<String>::deserialize(deserializer)?.and_then(|x| x.parse().ok()))
+}
+
 impl AircraftData {
     pub fn empty() -> Self {
         Self { time: 0, data: vec![] }
     }
+}
+
+impl From<i32> for PositionSource {
+    fn from(code: i32) -> Self {
+        match code {
+            0 => PositionSource::AdsB,
+            1 => PositionSource::Asterix,
+            2 => PositionSource::Mlat,
+            3 => PositionSource::Flarm,
+            _ => PositionSource::Unknown(code)
+        }
+    }
+}
+
+impl From<PositionSource> for i32 {
+    fn from(source: PositionSource) -> Self {
+        match source {
+            PositionSource::AdsB => 0,
+            PositionSource::Asterix => 1,
+            PositionSource::Mlat => 2,
+            PositionSource::Flarm => 3,
+            PositionSource::Unknown(code) => code
+        }
+    }
+}
+
+impl fmt::Display for PositionSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionSource::AdsB => write!(f, "ADS-B"),
+            PositionSource::Asterix => write!(f, "ASTERIX"),
+            PositionSource::Mlat => write!(f, "MLAT"),
+            PositionSource::Flarm => write!(f, "FLARM"),
+            PositionSource::Unknown(code) => write!(f, "Position source {}", code)
+        }
+    }
+}
+
+impl Squawk {
+    pub const HIJACK: Squawk = Squawk(0o7500);
+    pub const RADIO_FAILURE: Squawk = Squawk(0o7600);
+    pub const EMERGENCY: Squawk = Squawk(0o7700);
+}
+
+impl FromStr for Squawk {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let code = code.trim();
+        if code.len() != 4 || !code.chars().all(|c| ('0'..='7').contains(&c)) {
+            return Err(format!("Invalid squawk \"{}\"", code));
+        }
+
+        u16::from_str_radix(code, 8).map(Squawk).map_err(|e| e.to_string())
+    }
+}
+
+impl fmt::Display for Squawk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04o}", self.0)
+    }
+}
+
+impl Serialize for Squawk {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        serializer.collect_str(self)
+    }
+}
 
-    pub fn linear_search<P>(&self, pred: P) -> Option<&Aircraft>
-        where P: FnMut(&&Aircraft) -> bool {
-        self.data.iter()
-            .filter(pred)
-            .next()
+impl Extrapolation {
+    pub fn position_of(&self, aircraft: &Aircraft) -> Option<EstimatedPosition> {
+        aircraft.extrapolate(self.time, self.max_age)
     }
 }
 
 impl Aircraft {
+    // New state vector with no data beyond the address, for sources which build up state incrementally.  The
+    // country of registration is determined from the address, since these sources do not report it
+    pub fn new(icao24: &str, last_contact: i64) -> Self {
+        Self {
+            icao24: icao24.to_lowercase(),
+            callsign: None,
+            origin_country: icao_address::country(icao24).unwrap_or_default().to_string(),
+            time_position: None,
+            last_contact,
+            longitude: None,
+            latitude: None,
+            baro_altitude: None,
+            on_ground: false,
+            velocity: None,
+            true_track: None,
+            vertical_rate: None,
+            sensors: None,
+            geo_altitude: None,
+            squawk: None,
+            spi: false,
+            position_source: PositionSource::AdsB,
+            data_source: None
+        }
+    }
+
+    pub fn is_military(&self) -> bool {
+        icao_address::is_military(&self.icao24)
+    }
+
     pub fn _has_position_data(&self) -> bool {
         self.longitude.is_some() && self.latitude.is_some()
     }
 
-    pub fn basic_status(&self) -> String {
-        // e.g. "AAA1234 (United States, ICAO: ab42de, Last contact: 2 seconds ago)"
+    // Estimates the position at the given time by dead reckoning from the last reported position, along the
+    // current track at the current ground speed and vertical rate.  Extrapolates no further than `max_age`
+    // beyond the time of that report, and not at all if the aircraft is not moving or the time is unknown
+    pub fn extrapolate(&self, time: f64, max_age: Duration) -> Option<EstimatedPosition> {
+        let (longitude, latitude) = (self.longitude?, self.latitude?);
+        let altitude = self.baro_altitude.or(self.geo_altitude);
+
+        let age = self.time_position
+            .map(|t| (time - t as f64).clamp(0.0, max_age.as_secs_f64()))
+            .unwrap_or(0.0);
+
+        let (longitude, latitude) = match (self.velocity.map(|x| x.metres_per_sec() as f64), self.true_track) {
+            (Some(velocity), Some(track)) if age > 0.0 && velocity > 0.0 =>
+                greatcircle::destination((longitude, latitude), track as f64, velocity * age),
+            _ => (longitude, latitude)
+        };
+
+        let altitude = match (altitude, self.vertical_rate) {
+            (Some(alt), Some(rate)) if !self.on_ground =>
+                Some(Altitude::from_metres((alt.metres() + rate.metres_per_sec() * age as f32).max(0.0))),
+            _ => altitude
+        };
+
+        Some(EstimatedPosition { longitude, latitude, altitude })
+    }
+
+    pub fn basic_status(&self, units: UnitSystem) -> String {
+        // e.g. "AAA1234 (United States, Military, ICAO: ae42de, Last contact: 2 seconds ago) 36000ft, 450kt, -1024ft/min, Squawk 7700, ADS-B"
         let last_contact = temporal::get_duration(
             temporal::systemtime_from_datetime(
                 temporal::utc_datetime_from_timestamp(self.last_contact as i64)),
             SystemTime::now());
 
-        format!("{} ({}, ICAO: {}, Last contact: {})",
-            self.callsign.as_ref().unwrap_or(&"[Unknown callsign]".to_string()),
+        let details = vec![
+            self.baro_altitude.or(self.geo_altitude).map(|x| x.format(units)),
+            self.velocity.map(|x| x.format(units)),
+            self.vertical_rate.map(|x| x.format(units)),
+            self.squawk.map(|x| format!("Squawk {}", x)),
+            Some(self.position_source.to_string())
+        ];
+
+        format!("{} ({}{}, ICAO: {}, Last contact: {}) {}{}",
+            self.callsign.as_deref().map(str::trim).unwrap_or("[Unknown callsign]"),
             self.origin_country,
+            if self.is_military() { ", Military" } else { "" },
             self.icao24,
-            last_contact.map(|x| format!("{} seconds ago", x.as_secs())).unwrap_or("[Unknown]".to_string())
+            last_contact.map(|x| format!("{} seconds ago", x.as_secs())).unwrap_or("[Unknown]".to_string()),
+            details.into_iter().flatten().collect::<Vec<String>>().join(", "),
+            self.data_source.as_ref().map(|x| format!(" via {}", x)).unwrap_or_default()
         )
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aircraft, AircraftData, PositionSource, Squawk};
+    use crate::geo::greatcircle;
+    use crate::util::units::{Altitude, Speed, VerticalRate};
+    use std::time::Duration;
+
+    #[test]
+    fn test_extrapolation() {
+        let aircraft = Aircraft {
+            time_position: Some(1000), longitude: Some(-1.0), latitude: Some(51.0), baro_altitude: Some(Altitude::from_metres(3000.0)),
+            velocity: Some(Speed::from_metres_per_sec(200.0)), true_track: Some(90.0), vertical_rate: Some(VerticalRate::from_metres_per_sec(-5.0)),
+            ..Aircraft::new("4ca2d6", 1000)
+        };
+        let max_age = Duration::from_secs(30);
+
+        let at = |time| aircraft.extrapolate(time, max_age).unwrap();
+        let distance = |time| greatcircle::distance((-1.0, 51.0), (at(time).longitude, at(time).latitude));
+
+        assert!((distance(1010.0) - 2000.0).abs() < 1.0);
+        assert!(at(1010.0).longitude > -1.0);
+        assert_eq!(at(1010.0).altitude, Some(Altitude::from_metres(2950.0)));
+
+        // Capped at the maximum age, and never extrapolated backwards
+        assert!((distance(2000.0) - 6000.0).abs() < 1.0);
+        assert_eq!(at(990.0).longitude, -1.0);
+
+        // Aircraft without a track or position
+        let stationary = Aircraft { true_track: None, ..aircraft.clone() };
+        assert_eq!(stationary.extrapolate(1010.0, max_age).unwrap().longitude, -1.0);
+        assert!(Aircraft::new("406b90", 1000).extrapolate(1010.0, max_age).is_none());
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let json = r#"{"time":1566137050,"states":[
+            ["ac96b8","AAL137  ","United States",1566136785,1566136790,-97.0546,32.9235,228.6,false,72.02,180,-4.88,null,213.36,"0755",false,2],
+            ["4ca2d6","BAW123  ","United Kingdom",1566136785,1566136790,-0.4543,51.47,null,false,null,null,null,null,null,"8888",true,7]]}"#;
+        let data: AircraftData = serde_json::from_str(json).unwrap();
+
+        let a = &data.data[0];
+        assert_eq!(a.squawk, Some("0755".parse().unwrap()));
+        assert_eq!(a.position_source, PositionSource::Mlat);
+        assert!((a.baro_altitude.unwrap().feet() - 750.0).abs() < 0.01);
+        assert!((a.velocity.unwrap().knots() - 140.0).abs() < 0.01);
+
+        // Invalid codes are discarded, and unknown sources retained
+        let b = &data.data[1];
+        assert_eq!(b.squawk, None);
+        assert_eq!(b.position_source, PositionSource::Unknown(7));
+
+        // Round-trips in the same format
+        let serialised = serde_json::to_string(&data.data[0]).unwrap();
+        assert!(serialised.contains(r#""0755",false,2"#), "{}", serialised);
+        assert_eq!(&serde_json::from_str::<Aircraft>(&serialised).unwrap(), a);
+
+        assert_eq!("7700".parse::<Squawk>().unwrap(), Squawk::EMERGENCY);
+        assert_eq!(Squawk::HIJACK.to_string(), "7500");
+        assert!("7800".parse::<Squawk>().is_err());
+    }
+}
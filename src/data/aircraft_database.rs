@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::io;
+use crate::data::parsing;
+
+pub const DEFAULT_AIRCRAFT_DATABASE_PATH: &str = "resources/aircraftDatabase.csv";
+
+// Columns are located by name, accepting the names used by each version of the OpenSky database
+const COLUMN_ICAO24: &[&str] = &["icao24"];
+const COLUMN_REGISTRATION: &[&str] = &["registration"];
+const COLUMN_MANUFACTURER: &[&str] = &["manufacturername", "manufacturer"];
+const COLUMN_MODEL: &[&str] = &["model"];
+const COLUMN_TYPECODE: &[&str] = &["typecode"];
+const COLUMN_OPERATOR: &[&str] = &["operator", "owner"];
+
+// Registration and type details of a single airframe.  Any field may be missing from the database
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AircraftInfo {
+    pub registration: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub typecode: Option<String>,           // ICAO aircraft type designator, e.g. "A319"
+    pub operator: Option<String>,
+    search_text: String                     // All fields, lowercased once for matching against search terms
+}
+
+// Aircraft metadata indexed by icao24, in the layout of the OpenSky `aircraftDatabase.csv`
+#[derive(Debug, Default)]
+pub struct AircraftDatabase {
+    entries: HashMap<String, AircraftInfo>
+}
+
+impl AircraftInfo {
+    // e.g. "G-EUPT, Airbus A319 131 (A319), British Airways"
+    pub fn summary(&self) -> String {
+        let aircraft_type = match (&self.manufacturer, &self.model) {
+            (Some(manufacturer), Some(model)) if model.starts_with(manufacturer.as_str()) => Some(model.clone()),
+            (Some(manufacturer), Some(model)) => Some(format!("{} {}", manufacturer, model)),
+            (manufacturer, model) => model.clone().or_else(|| manufacturer.clone())
+        };
+
+        vec![
+            self.registration.clone(),
+            match (aircraft_type, &self.typecode) {
+                (Some(t), Some(code)) => Some(format!("{} ({})", t, code)),
+                (t, code) => t.or_else(|| code.clone())
+            },
+            self.operator.clone()
+        ].into_iter().flatten().collect::<Vec<String>>().join(", ")
+    }
+
+    // All text fields in lowercase, separated by newlines so that search terms cannot match across fields
+    pub fn search_text(&self) -> &str {
+        &self.search_text
+    }
+
+    fn with_search_text(self) -> Self {
+        let search_text = vec![&self.registration, &self.manufacturer, &self.model, &self.typecode, &self.operator]
+            .into_iter()
+            .flatten()
+            .map(|x| x.to_lowercase())
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        Self { search_text, ..self }
+    }
+}
+
+impl AircraftDatabase {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        std::fs::read_to_string(path)
+            .and_then(|x| Self::parse(&x).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+    }
+
+    // Parses the full CSV content, including its header row.  Records without an address are skipped
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let mut lines = data.lines();
+        let header = parsing::parse_csv_record(lines.next().unwrap_or_default());
+
+        let column = |names: &[&str]| header.iter().position(|x| names.contains(&x.trim().to_ascii_lowercase().as_str()));
+        let icao24 = column(COLUMN_ICAO24).ok_or_else(|| "No \"icao24\" column".to_string())?;
+        let columns = [COLUMN_REGISTRATION, COLUMN_MANUFACTURER, COLUMN_MODEL, COLUMN_TYPECODE, COLUMN_OPERATOR]
+            .iter()
+            .map(|x| column(x))
+            .collect::<Vec<Option<usize>>>();
+
+        let entries = lines
+            .map(parsing::parse_csv_record)
+            .filter(|x| x.get(icao24).map(|x| !x.trim().is_empty()).unwrap_or(false))
+            .map(|record| {
+                let field = |ix: usize| columns[ix]
+                    .and_then(|col| record.get(col))
+                    .map(|x| x.trim().to_string())
+                    .filter(|x| !x.is_empty());
+
+                (record[icao24].trim().to_lowercase(), AircraftInfo {
+                    registration: field(0),
+                    manufacturer: field(1),
+                    model: field(2),
+                    typecode: field(3),
+                    operator: field(4),
+                    ..AircraftInfo::default()
+                }.with_search_text())
+            })
+            .collect::<HashMap<String, AircraftInfo>>();
+
+        Ok(Self { entries })
+    }
+
+    pub fn get(&self, icao24: &str) -> Option<&AircraftInfo> {
+        self.entries.get(icao24)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+// Loads the database if present; it is optional, since it is large and not distributed with the application
+pub fn load_aircraft_database(path: Option<&str>) -> AircraftDatabase {
+    let path = path.unwrap_or(DEFAULT_AIRCRAFT_DATABASE_PATH);
+    match AircraftDatabase::load(path) {
+        Ok(db) => {
+            println!("Loaded details of {} aircraft from \"{}\"", db.len(), path);
+            db
+        },
+        Err(e) => {
+            println!("No aircraft database available at \"{}\" ({})", path, e);
+            AircraftDatabase::empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AircraftDatabase, AircraftInfo};
+
+    const DATABASE: &str = "\
+'icao24','registration','manufacturericao','manufacturername','model','typecode','serialnumber','operator'
+'4ca2d6','EI-DVM','BOEING','Boeing','737-8AS','B738','33639','Ryanair'
+'ac96b8','N917NN','','','Boeing 737-823','B738','',''
+'','N1','','','','','',''
+'A0B1C2','','','','','','',''
+";
+
+    #[test]
+    fn test_database_lookup() {
+        let db = AircraftDatabase::parse(DATABASE).unwrap();
+        assert_eq!(db.len(), 3);
+
+        let ryanair = db.get("4ca2d6").unwrap();
+        assert_eq!(ryanair.registration.as_deref(), Some("EI-DVM"));
+        assert_eq!(ryanair.summary(), "EI-DVM, Boeing 737-8AS (B738), Ryanair");
+        assert_eq!(db.get("ac96b8").unwrap().summary(), "N917NN, Boeing 737-823 (B738)");
+        assert_eq!(ryanair.search_text(), "ei-dvm\nboeing\n737-8as\nb738\nryanair");
+        assert_eq!(db.get("a0b1c2"), Some(&AircraftInfo::default()));
+        assert!(db.get("406b90").is_none());
+
+        assert!(AircraftDatabase::parse("registration,model\nG-EUPT,A319").is_err());
+    }
+}
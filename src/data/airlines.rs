@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::data::parsing;
+
+const AIRLINE_DATA_PATH: &str = "resources/airlines.csv";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Airline {
+    pub icao: String,                       // Three-letter operator designator, e.g. "AAL"
+    pub iata: Option<String>,               // Two-character designator used in flight numbers, e.g. "AA"
+    pub name: String,
+    pub telephony: Option<String>,          // Radio callsign, e.g. "AMERICAN"
+    pub country: Option<String>
+}
+
+// Airlines indexed by ICAO designator
+pub struct AirlineTable {
+    airlines: HashMap<String, Airline>
+}
+
+// Callsign of the form used by airline flights, i.e. an ICAO operator designator followed by the flight
+// identifier, e.g. "AAL137" or "EZY83TL".  Other callsigns, e.g. registrations, are not of this form
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightCallsign {
+    pub operator: String,
+    pub flight: String
+}
+
+// Flight callsign resolved to its airline, e.g. "AAL137 → American Airlines AA137"
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirlineFlight<'a> {
+    pub callsign: FlightCallsign,
+    pub airline: &'a Airline
+}
+
+impl FlightCallsign {
+    pub fn parse(callsign: &str) -> Option<Self> {
+        let callsign = callsign.trim();
+        if callsign.len() < 4 || callsign.len() > 8 || !callsign.is_ascii() {
+            return None;
+        }
+
+        let (operator, flight) = callsign.split_at(3);
+        let valid = operator.chars().all(|c| c.is_ascii_alphabetic()) &&
+            flight.starts_with(|c: char| c.is_ascii_digit()) &&
+            flight.chars().all(|c| c.is_ascii_alphanumeric());
+
+        if valid {
+            Some(Self { operator: operator.to_ascii_uppercase(), flight: flight.to_ascii_uppercase() })
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for FlightCallsign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.operator, self.flight)
+    }
+}
+
+impl<'a> AirlineFlight<'a> {
+    // Equivalent IATA flight number, e.g. "AA137" for "AAL137".  Only numeric flight identifiers have an
+    // equivalent; alphanumeric identifiers, e.g. "EZY83TL", are assigned independently of the flight number
+    pub fn iata_flight_number(&self) -> Option<String> {
+        let number = self.callsign.flight.trim_start_matches('0');
+        match &self.airline.iata {
+            Some(iata) if self.callsign.flight.chars().all(|c| c.is_ascii_digit()) =>
+                Some(format!("{}{}", iata, if number.is_empty() { "0" } else { number })),
+            _ => None
+        }
+    }
+
+    // e.g. "American Airlines AA137"
+    pub fn description(&self) -> String {
+        match self.iata_flight_number() {
+            Some(number) => format!("{} {}", self.airline.name, number),
+            None => self.airline.name.clone()
+        }
+    }
+}
+
+impl<'a> fmt::Display for AirlineFlight<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} \u{2192} {}", self.callsign, self.description())
+    }
+}
+
+impl AirlineTable {
+    // Parses CSV content with a header row, of the form "icao,iata,name,callsign,country"
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let mut lines = data.lines();
+        let header = parsing::parse_csv_record(lines.next().unwrap_or_default());
+        let column = |name: &str| header.iter().position(|x| x.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("No \"{}\" column", name));
+
+        let (icao, iata, name) = (column("icao")?, column("iata")?, column("name")?);
+        let (telephony, country) = (column("callsign").ok(), column("country").ok());
+
+        let airlines = lines
+            .filter(|x| !x.trim().is_empty())
+            .map(parsing::parse_csv_record)
+            .map(|record| {
+                let field = |ix: Option<usize>| ix
+                    .and_then(|ix| record.get(ix))
+                    .map(|x| x.trim().to_string())
+                    .filter(|x| !x.is_empty());
+
+                match (field(Some(icao)), field(Some(name))) {
+                    (Some(icao), Some(name)) => Ok(Airline {
+                        icao: icao.to_ascii_uppercase(),
+                        iata: field(Some(iata)),
+                        name,
+                        telephony: field(telephony),
+                        country: field(country)
+                    }),
+                    _ => Err(format!("Invalid airline record ({:?})", record))
+                }
+            })
+            .map(|x| x.map(|airline| (airline.icao.clone(), airline)))
+            .collect::<Result<HashMap<String, Airline>, String>>()?;
+
+        Ok(Self { airlines })
+    }
+
+    pub fn get(&self, icao: &str) -> Option<&Airline> {
+        self.airlines.get(icao)
+    }
+
+    // Resolves the callsign to an airline flight, if it is of that form and the operator is known
+    pub fn resolve(&self, callsign: &str) -> Option<AirlineFlight<'_>> {
+        let callsign = FlightCallsign::parse(callsign)?;
+        let airline = self.get(&callsign.operator)?;
+
+        Some(AirlineFlight { callsign, airline })
+    }
+}
+
+pub fn load_airline_data() -> AirlineTable {
+    AirlineTable::parse(&std::fs::read_to_string(AIRLINE_DATA_PATH)
+        .expect("Failed to load airline data"))
+        .unwrap_or_else(|e| panic!("Failed to parse airline data ({})", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AirlineTable, FlightCallsign, AIRLINE_DATA_PATH};
+
+    #[test]
+    fn test_callsign_parsing() {
+        assert_eq!(FlightCallsign::parse("AAL137  "), Some(FlightCallsign { operator: "AAL".to_string(), flight: "137".to_string() }));
+        assert_eq!(FlightCallsign::parse("ezy83tl").map(|x| x.to_string()), Some("EZY83TL".to_string()));
+        assert_eq!(FlightCallsign::parse("GEUPT"), None);
+        assert_eq!(FlightCallsign::parse("N917NN"), None);
+        assert_eq!(FlightCallsign::parse("BAW"), None);
+        assert_eq!(FlightCallsign::parse("BAW12345A"), None);
+    }
+
+    #[test]
+    fn test_airline_resolution() {
+        let table = AirlineTable::parse(&std::fs::read_to_string(AIRLINE_DATA_PATH).unwrap()).unwrap();
+
+        assert_eq!(table.resolve("AAL137  ").unwrap().to_string(), "AAL137 \u{2192} American Airlines AA137");
+        assert_eq!(table.resolve("BAW012").unwrap().description(), "British Airways BA12");
+        assert_eq!(table.resolve("EZY83TL").unwrap().description(), "easyJet");
+        assert_eq!(table.get("RYR").unwrap().telephony.as_deref(), Some("RYANAIR"));
+        assert!(table.resolve("XXX123").is_none());
+        assert!(table.resolve("N917NN").is_none());
+
+        assert!(AirlineTable::parse("icao,name\nAAL,American Airlines").is_err());
+        assert!(AirlineTable::parse("icao,iata,name\nAAL,AA,").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::data::flight::{Flight, FlightData};
+
+// Recent arrivals and departures at an airport, as retrieved for its board
+#[derive(Debug, Clone)]
+pub struct AirportBoard {
+    pub airport: String,                    // ICAO identifier, e.g. "EGLL"
+    pub arrivals: FlightData,
+    pub departures: FlightData,
+    pub time: i64                           // Time of retrieval
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Movement {
+    Arrival,
+    Departure
+}
+
+// Single line of the board, i.e. one flight arriving at or departing from the airport
+#[derive(Debug, Clone, Copy)]
+pub struct BoardEntry<'a> {
+    pub movement: Movement,
+    pub flight: &'a Flight
+}
+
+impl AirportBoard {
+    // Arrivals or departures, most recent first
+    pub fn entries(&self, movement: Movement) -> Vec<BoardEntry<'_>> {
+        let flights = match movement {
+            Movement::Arrival => &self.arrivals,
+            Movement::Departure => &self.departures
+        };
+
+        let mut entries = flights.iter()
+            .map(|flight| BoardEntry { movement, flight })
+            .collect::<Vec<BoardEntry>>();
+
+        entries.sort_by_key(|x| std::cmp::Reverse(x.time()));
+        entries
+    }
+}
+
+impl<'a> BoardEntry<'a> {
+    // Time of the movement at this airport; aircraft are last seen on arrival, and first seen on departure
+    pub fn time(&self) -> i64 {
+        match self.movement {
+            Movement::Arrival => self.flight.last_seen,
+            Movement::Departure => self.flight.first_seen
+        }
+    }
+
+    // Airport at the other end of the flight, i.e. the origin of an arrival or the destination of a departure
+    pub fn counterpart(&self) -> Option<&'a str> {
+        match self.movement {
+            Movement::Arrival => self.flight.departure(),
+            Movement::Departure => self.flight.arrival()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AirportBoard, Movement};
+    use crate::data::flight::FlightData;
+
+    #[test]
+    fn test_board_entries() {
+        let flights = |json: &str| serde_json::from_str::<FlightData>(json).unwrap();
+        let board = AirportBoard {
+            airport: "EGLL".to_string(),
+            arrivals: flights(r#"[
+                {"icao24":"4ca2d6","firstSeen":1566100000,"estDepartureAirport":"EIDW","lastSeen":1566104000,"estArrivalAirport":"EGLL","callsign":"RYR4GP  "},
+                {"icao24":"ac96b8","firstSeen":1566080000,"estDepartureAirport":null,"lastSeen":1566106000,"estArrivalAirport":"EGLL","callsign":"AAL136  "}]"#),
+            departures: flights(r#"[
+                {"icao24":"406b90","firstSeen":1566103000,"estDepartureAirport":"EGLL","lastSeen":1566130000,"estArrivalAirport":"KJFK","callsign":"BAW123  "}]"#),
+            time: 1566110000
+        };
+
+        let arrivals = board.entries(Movement::Arrival);
+        assert_eq!(arrivals.iter().map(|x| x.time()).collect::<Vec<i64>>(), vec![1566106000, 1566104000]);
+        assert_eq!(arrivals.iter().map(|x| x.counterpart()).collect::<Vec<Option<&str>>>(), vec![None, Some("EIDW")]);
+
+        let departures = board.entries(Movement::Departure);
+        assert_eq!((departures[0].time(), departures[0].counterpart()), (1566103000, Some("KJFK")));
+        assert_eq!(departures[0].movement, Movement::Departure);
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::io;
+use crate::data::parsing;
+use crate::util::units::{self, Altitude};
+
+pub const DEFAULT_AIRPORTS_PATH: &str = "resources/airports.csv";
+pub const DEFAULT_RUNWAYS_PATH: &str = "resources/runways.csv";
+
+// Zoom level from which each size of airport is shown on the map
+const MIN_ZOOM_LARGE: f64 = 0.0;
+const MIN_ZOOM_MEDIUM: f64 = 4.0;
+const MIN_ZOOM_SMALL: f64 = 16.0;
+const MIN_ZOOM_MINOR: f64 = 64.0;
+
+// Classification used by OurAirports, in descending order of size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AirportType {
+    Large,
+    Medium,
+    Small,
+    Heliport,
+    SeaplaneBase,
+    Balloonport,
+    Closed,
+    Other
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Runway {
+    pub le_ident: Option<String>,           // Designator of the low-numbered end, e.g. "09L"
+    pub he_ident: Option<String>,           // Designator of the high-numbered end, e.g. "27R"
+    pub length: Option<f32>,                // Metres
+    pub surface: Option<String>,
+    pub closed: bool
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Airport {
+    pub ident: String,                      // ICAO code where one is assigned, e.g. "EGLL"
+    pub name: String,
+    pub airport_type: AirportType,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub elevation: Option<Altitude>,
+    pub iata: Option<String>,
+    pub municipality: Option<String>,
+    pub runways: Vec<Runway>
+}
+
+// All airports, indexed by ident and by GPS code where that differs
+#[derive(Debug, Default)]
+pub struct AirportDatabase {
+    airports: Vec<Airport>,                 // Largest first
+    index: HashMap<String, usize>
+}
+
+impl AirportType {
+    pub fn parse(name: &str) -> Self {
+        match name.trim() {
+            "large_airport" => AirportType::Large,
+            "medium_airport" => AirportType::Medium,
+            "small_airport" => AirportType::Small,
+            "heliport" => AirportType::Heliport,
+            "seaplane_base" => AirportType::SeaplaneBase,
+            "balloonport" => AirportType::Balloonport,
+            "closed" => AirportType::Closed,
+            _ => AirportType::Other
+        }
+    }
+
+    // Zoom level from which airports of this type are shown, if at all
+    pub fn min_zoom(&self) -> Option<f64> {
+        match self {
+            AirportType::Large => Some(MIN_ZOOM_LARGE),
+            AirportType::Medium => Some(MIN_ZOOM_MEDIUM),
+            AirportType::Small => Some(MIN_ZOOM_SMALL),
+            AirportType::Heliport | AirportType::SeaplaneBase | AirportType::Balloonport => Some(MIN_ZOOM_MINOR),
+            AirportType::Closed | AirportType::Other => None
+        }
+    }
+}
+
+impl Runway {
+    // e.g. "09L/27R"
+    pub fn designation(&self) -> String {
+        vec![self.le_ident.clone(), self.he_ident.clone()].into_iter().flatten().collect::<Vec<String>>().join("/")
+    }
+}
+
+impl Airport {
+    pub fn is_visible_at(&self, zoom_level: f64) -> bool {
+        self.airport_type.min_zoom().map(|x| zoom_level >= x).unwrap_or(false)
+    }
+
+    // e.g. "EGLL London Heathrow Airport"
+    pub fn summary(&self) -> String {
+        format!("{} {}", self.ident, self.name)
+    }
+
+    // Summary with any open runways, e.g. "EGLL London Heathrow Airport (09L/27R, 09R/27L)"
+    pub fn detailed_summary(&self) -> String {
+        let runways = self.runways.iter()
+            .filter(|x| !x.closed)
+            .map(|x| x.designation())
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<String>>();
+
+        if runways.is_empty() {
+            self.summary()
+        } else {
+            format!("{} ({})", self.summary(), runways.join(", "))
+        }
+    }
+}
+
+impl AirportDatabase {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    // Runways are optional, and held in a separate file
+    pub fn load(airports_path: &str, runways_path: Option<&str>) -> io::Result<Self> {
+        let airports = std::fs::read_to_string(airports_path)?;
+        let runways = runways_path
+            .and_then(|path| std::fs::read_to_string(path)
+                .map_err(|e| println!("No runway data available at \"{}\" ({})", path, e))
+                .ok());
+
+        Self::parse(&airports, runways.as_deref())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    // Parses content in the layout of the OurAirports `airports.csv` and `runways.csv` files, including header rows
+    pub fn parse(airports_data: &str, runways_data: Option<&str>) -> Result<Self, String> {
+        let mut runways = match runways_data {
+            Some(data) => parse_runways(data)?,
+            None => HashMap::new()
+        };
+
+        let (header, records) = parse_records(airports_data)?;
+        let column = |name: &str| header.get(name).copied();
+        let required = |name: &str| column(name).ok_or_else(|| format!("No \"{}\" column", name));
+        let (ident, name, kind) = (required("ident")?, required("name")?, required("type")?);
+        let (lat, lon) = (required("latitude_deg")?, required("longitude_deg")?);
+        let (elevation, iata, municipality, gps_code) = (column("elevation_ft"), column("iata_code"), column("municipality"), column("gps_code"));
+
+        let mut airports = vec![];
+        for record in records {
+            let field = |ix: Option<usize>| ix
+                .and_then(|ix| record.get(ix))
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty());
+
+            let (longitude, latitude) = match (field(Some(lon)).and_then(|x| x.parse::<f64>().ok()),
+                                               field(Some(lat)).and_then(|x| x.parse::<f64>().ok())) {
+                (Some(lon), Some(lat)) => (lon, lat),
+                _ => continue
+            };
+            let ident = match field(Some(ident)) {
+                Some(x) => x.to_ascii_uppercase(),
+                None => continue
+            };
+
+            let airport = Airport {
+                runways: runways.remove(&ident).unwrap_or_default(),
+                ident,
+                name: field(Some(name)).unwrap_or_default().to_string(),
+                airport_type: AirportType::parse(field(Some(kind)).unwrap_or_default()),
+                longitude,
+                latitude,
+                elevation: field(elevation).and_then(|x| x.parse::<f32>().ok()).map(Altitude::from_feet),
+                iata: field(iata).map(str::to_string),
+                municipality: field(municipality).map(str::to_string)
+            };
+
+            airports.push((airport, field(gps_code).map(|x| x.to_ascii_uppercase())));
+        }
+
+        let mut db = Self::empty();
+        airports.sort_by_key(|(x, _)| x.airport_type);
+        airports.into_iter().for_each(|(airport, gps_code)| db.insert(airport, gps_code));
+
+        Ok(db)
+    }
+
+    fn insert(&mut self, airport: Airport, gps_code: Option<String>) {
+        let ix = self.airports.len();
+        self.index.insert(airport.ident.clone(), ix);
+        if let Some(code) = gps_code {
+            self.index.entry(code).or_insert(ix);
+        }
+
+        self.airports.push(airport);
+    }
+
+    pub fn get(&self, ident: &str) -> Option<&Airport> {
+        self.index.get(&ident.trim().to_ascii_uppercase()).map(|&ix| &self.airports[ix])
+    }
+
+    pub fn len(&self) -> usize {
+        self.airports.len()
+    }
+
+    // All airports shown at this zoom level, largest first
+    pub fn visible_at(&self, zoom_level: f64) -> impl Iterator<Item = &Airport> {
+        self.airports.iter().filter(move |x| x.is_visible_at(zoom_level))
+    }
+}
+
+// Header row, as a map from column name to index, and all subsequent non-empty records
+type Records = (HashMap<String, usize>, Vec<Vec<String>>);
+
+fn parse_records(data: &str) -> Result<Records, String> {
+    let mut lines = data.lines().filter(|x| !x.trim().is_empty());
+    let header = lines.next()
+        .map(parsing::parse_csv_record)
+        .ok_or_else(|| "No header row".to_string())?
+        .into_iter()
+        .enumerate()
+        .map(|(ix, name)| (name.trim().to_ascii_lowercase(), ix))
+        .collect::<HashMap<String, usize>>();
+
+    Ok((header, lines.map(parsing::parse_csv_record).collect()))
+}
+
+// Runways, indexed by the ident of their airport
+fn parse_runways(data: &str) -> Result<HashMap<String, Vec<Runway>>, String> {
+    let (header, records) = parse_records(data)?;
+    let column = |name: &str| header.get(name).copied();
+    let airport = column("airport_ident").ok_or_else(|| "No \"airport_ident\" column".to_string())?;
+
+    let mut runways: HashMap<String, Vec<Runway>> = HashMap::new();
+    for record in records {
+        let field = |name: &str| column(name)
+            .and_then(|ix| record.get(ix))
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty());
+
+        if let Some(ident) = record.get(airport).map(|x| x.trim().to_ascii_uppercase()).filter(|x| !x.is_empty()) {
+            runways.entry(ident).or_default().push(Runway {
+                le_ident: field("le_ident"),
+                he_ident: field("he_ident"),
+                length: field("length_ft").and_then(|x| x.parse::<f32>().ok()).map(units::feet_to_metres),
+                surface: field("surface"),
+                closed: field("closed").map(|x| x == "1").unwrap_or(false)
+            });
+        }
+    }
+
+    Ok(runways)
+}
+
+// Loads the airport database if present; like the aircraft database, it is large and not distributed with the application
+pub fn load_airports(airports_path: Option<&str>, runways_path: Option<&str>) -> AirportDatabase {
+    let airports_path = airports_path.unwrap_or(DEFAULT_AIRPORTS_PATH);
+    match AirportDatabase::load(airports_path, Some(runways_path.unwrap_or(DEFAULT_RUNWAYS_PATH))) {
+        Ok(db) => {
+            println!("Loaded {} airports from \"{}\"", db.len(), airports_path);
+            db
+        },
+        Err(e) => {
+            println!("No airport data available at \"{}\" ({})", airports_path, e);
+            AirportDatabase::empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AirportDatabase, AirportType};
+
+    const AIRPORTS: &str = r#""id","ident","type","name","latitude_deg","longitude_deg","elevation_ft","continent","iso_country","iso_region","municipality","scheduled_service","gps_code","iata_code","local_code"
+2434,"EGLL","large_airport","London Heathrow Airport",51.4706,-0.461941,83,"EU","GB","GB-ENG","London","yes","EGLL","LHR",
+2429,"EGGW","medium_airport","London Luton Airport",51.874699,-0.368333,526,"EU","GB","GB-ENG","London","yes","EGGW","LTN",
+325045,"GB-0370","small_airport","Hinton-in-the-Hedges Airfield",52.0281,-1.2089,505,"EU","GB","GB-ENG","Brackley","no","EGKH",,
+29,"EGXX","closed","Old Airfield",52.0,-1.0,,"EU","GB","GB-ENG",,"no",,,
+30,"EGYY","small_airport","No Position",,,,"EU","GB","GB-ENG",,"no",,,
+"#;
+
+    const RUNWAYS: &str = r#""id","airport_ref","airport_ident","length_ft","width_ft","surface","lighted","closed","le_ident","he_ident"
+232758,2434,"EGLL",12799,164,"ASP",1,0,"09L","27R"
+232759,2434,"EGLL",12008,164,"ASP",1,0,"09R","27L"
+"#;
+
+    #[test]
+    fn test_airport_database() {
+        let db = AirportDatabase::parse(AIRPORTS, Some(RUNWAYS)).unwrap();
+        assert_eq!(db.len(), 4);
+
+        let heathrow = db.get("egll").unwrap();
+        assert_eq!(heathrow.summary(), "EGLL London Heathrow Airport");
+        assert_eq!(heathrow.airport_type, AirportType::Large);
+        assert_eq!(heathrow.iata.as_deref(), Some("LHR"));
+        assert!((heathrow.elevation.unwrap().feet() - 83.0).abs() < 0.01);
+        assert_eq!(heathrow.detailed_summary(), "EGLL London Heathrow Airport (09L/27R, 09R/27L)");
+        assert!((heathrow.runways[0].length.unwrap() - 3901.1).abs() < 0.1);
+
+        // Airports are also found by GPS code where the ident is not an ICAO code
+        assert_eq!(db.get("EGKH").unwrap().ident, "GB-0370");
+        assert!(db.get("EGYY").is_none());
+
+        let visible = |zoom| db.visible_at(zoom).map(|x| x.ident.as_str()).collect::<Vec<&str>>();
+        assert_eq!(visible(1.0), vec!["EGLL"]);
+        assert_eq!(visible(4.0), vec!["EGLL", "EGGW"]);
+        assert_eq!(visible(100.0), vec!["EGLL", "EGGW", "GB-0370"]);
+
+        assert!(AirportDatabase::parse("ident,name\nEGLL,Heathrow", None).is_err());
+    }
+}
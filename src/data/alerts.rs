@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use crate::data::aircraft::{Aircraft, Squawk};
+use crate::data::store::{AircraftStore, AircraftEvent};
+use crate::util::temporal::utc_datetime_from_timestamp;
+use crate::util::units::{Altitude, UnitSystem};
+
+// In descending order of severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AlertKind {
+    Hijack,                                 // Squawk 7500
+    Emergency,                              // Squawk 7700
+    RadioFailure,                           // Squawk 7600
+    Ident                                   // Special purpose indicator, set by the crew on request from ATC
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub raised_at: isize,                   // Time of the snapshot in which the alert was first seen
+    pub position: Option<(f64, f64)>,       // (lon, lat) when raised
+    pub altitude: Option<Altitude>          // When raised
+}
+
+// Tracks alerts raised by all aircraft, reporting each as it is raised and cleared and optionally
+// appending the same to a log file
+pub struct AlertMonitor {
+    active: HashMap<String, Vec<Alert>>,    // Indexed by icao24
+    log: Option<File>
+}
+
+impl AlertKind {
+    pub fn detect(aircraft: &Aircraft) -> Vec<AlertKind> {
+        let squawk = [AlertKind::Hijack, AlertKind::Emergency, AlertKind::RadioFailure].iter()
+            .find(|x| x.squawk() == aircraft.squawk)
+            .copied();
+
+        squawk.into_iter()
+            .chain(Some(AlertKind::Ident).filter(|_| aircraft.spi))
+            .collect()
+    }
+
+    // Emergency squawk which raises this alert, if any
+    pub fn squawk(&self) -> Option<Squawk> {
+        match self {
+            AlertKind::Hijack => Some(Squawk::HIJACK),
+            AlertKind::Emergency => Some(Squawk::EMERGENCY),
+            AlertKind::RadioFailure => Some(Squawk::RADIO_FAILURE),
+            AlertKind::Ident => None
+        }
+    }
+
+    // e.g. "7700", or "SPI" for an ident
+    pub fn code(&self) -> String {
+        self.squawk().map(|x| x.to_string()).unwrap_or("SPI".to_string())
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            AlertKind::Hijack => "Unlawful interference",
+            AlertKind::Emergency => "General emergency",
+            AlertKind::RadioFailure => "Radio failure",
+            AlertKind::Ident => "Ident"
+        }
+    }
+}
+
+impl Alert {
+    fn new(kind: AlertKind, aircraft: &Aircraft, time: isize) -> Self {
+        Self {
+            kind,
+            icao24: aircraft.icao24.clone(),
+            callsign: aircraft.callsign.as_ref().map(|x| x.trim().to_string()).filter(|x| !x.is_empty()),
+            raised_at: time,
+            position: aircraft.longitude.and_then(|lon| aircraft.latitude.map(|lat| (lon, lat))),
+            altitude: aircraft.baro_altitude.or(aircraft.geo_altitude)
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        // e.g. "7700 General emergency: BAW123 (4ca2d6)"
+        format!("{} {}: {} ({})", self.kind.code(), self.kind.description(),
+                self.callsign.as_deref().unwrap_or("[Unknown callsign]"), self.icao24)
+    }
+}
+
+impl AlertMonitor {
+    // Alerts are appended to the log file, if any, so that it can be followed by other tools
+    pub fn new(log_path: Option<&str>) -> io::Result<Self> {
+        let log = match log_path {
+            Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+            None => None
+        };
+
+        Ok(Self { active: HashMap::new(), log })
+    }
+
+    // All active alerts, most severe first, then in the order in which they were raised
+    pub fn active(&self) -> Vec<&Alert> {
+        let mut alerts = self.active.values().flatten().collect::<Vec<&Alert>>();
+        alerts.sort_by(|a, b| a.kind.cmp(&b.kind)
+            .then(a.raised_at.cmp(&b.raised_at))
+            .then(a.icao24.cmp(&b.icao24)));
+        alerts
+    }
+
+    pub fn is_alerting(&self, icao24: &str) -> bool {
+        self.active.contains_key(icao24)
+    }
+
+    // Raises or clears the alerts of each aircraft which has changed in the latest snapshot, reporting each in
+    // the given units.  Alerts of aircraft which have only left the view remain active, since they are not lost
+    pub fn process(&mut self, events: &[AircraftEvent], store: &AircraftStore, units: UnitSystem) {
+        for event in events {
+            match event {
+                AircraftEvent::Added(icao24) | AircraftEvent::Updated(icao24) => {
+                    if let Some(aircraft) = store.get(icao24) {
+                        self.update(aircraft, store.time(), units);
+                    }
+                },
+                AircraftEvent::Lost(icao24) => {
+                    for alert in self.active.remove(icao24).unwrap_or_default() {
+                        self.report("LOST", &alert, store.time(), units);
+                    }
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, aircraft: &Aircraft, time: isize, units: UnitSystem) {
+        let kinds = AlertKind::detect(aircraft);
+        let previous = self.active.remove(&aircraft.icao24).unwrap_or_default();
+
+        let (current, cleared): (Vec<Alert>, Vec<Alert>) = previous.into_iter()
+            .partition(|x| kinds.contains(&x.kind));
+        let raised = kinds.iter()
+            .filter(|&kind| !current.iter().any(|x| x.kind == *kind))
+            .map(|&kind| Alert::new(kind, aircraft, time))
+            .collect::<Vec<Alert>>();
+
+        cleared.iter().for_each(|x| self.report("CLEARED", x, time, units));
+        raised.iter().for_each(|x| self.report("RAISED", x, time, units));
+
+        let active = current.into_iter().chain(raised).collect::<Vec<Alert>>();
+        if !active.is_empty() {
+            self.active.insert(aircraft.icao24.clone(), active);
+        }
+    }
+
+    fn report(&mut self, action: &str, alert: &Alert, time: isize, units: UnitSystem) {
+        // e.g. "[2019-08-18 14:04:10 UTC] RAISED 7700 General emergency: BAW123 (4ca2d6) at 51.4700,-0.4543, 3000m"
+        let entry = format!("[{}] {} {} at {}, {}",
+            utc_datetime_from_timestamp(time as i64).format("%Y-%m-%d %H:%M:%S UTC"),
+            action,
+            alert.summary(),
+            alert.position.map(|(lon, lat)| format!("{:.4},{:.4}", lat, lon)).unwrap_or("[Unknown position]".to_string()),
+            alert.altitude.map(|x| x.format(units)).unwrap_or("[Unknown altitude]".to_string()));
+
+        println!("{}", entry);
+        if let Some(log) = &mut self.log {
+            writeln!(log, "{}", entry).unwrap_or_else(|e| eprintln!("Failed to write to alert log ({})", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlertMonitor, AlertKind};
+    use crate::data::aircraft::{Aircraft, AircraftData};
+    use crate::data::store::AircraftStore;
+    use crate::geo::bounds::GeoBounds;
+    use crate::util::units::{Altitude, UnitSystem};
+
+    fn aircraft(icao24: &str, squawk: Option<&str>, spi: bool) -> Aircraft {
+        Aircraft { squawk: squawk.and_then(|x| x.parse().ok()), spi, callsign: Some("BAW123  ".to_string()),
+                   longitude: Some(-0.4543), latitude: Some(51.47), baro_altitude: Some(Altitude::from_metres(3000.0)),
+                   ..Aircraft::new(icao24, 1000) }
+    }
+
+    #[test]
+    fn test_detection() {
+        assert_eq!(AlertKind::detect(&aircraft("4ca2d6", Some("7700"), false)), vec![AlertKind::Emergency]);
+        assert_eq!(AlertKind::detect(&aircraft("4ca2d6", Some("7500"), true)), vec![AlertKind::Hijack, AlertKind::Ident]);
+        assert_eq!(AlertKind::detect(&aircraft("4ca2d6", Some("7000"), false)), vec![]);
+        assert_eq!(AlertKind::detect(&aircraft("4ca2d6", None, false)), vec![]);
+    }
+
+    #[test]
+    fn test_alerts_raised_and_cleared() {
+        let path = std::env::temp_dir().join(format!("flight-radar-alerts-{}.log", std::process::id()));
+        std::fs::remove_file(&path).unwrap_or(());
+
+        let mut store = AircraftStore::new();
+        let mut monitor = AlertMonitor::new(path.to_str()).unwrap();
+        let mut apply = |time, data: Vec<Aircraft>| {
+            let events = store.apply(AircraftData { time, data }, None);
+            monitor.process(&events, &store, UnitSystem::Metric);
+            monitor.active().iter().map(|x| (x.icao24.clone(), x.kind)).collect::<Vec<(String, AlertKind)>>()
+        };
+
+        assert_eq!(apply(1000, vec![aircraft("4ca2d6", Some("7600"), false), aircraft("406b90", Some("7000"), false)]),
+                   vec![("4ca2d6".to_string(), AlertKind::RadioFailure)]);
+        assert_eq!(apply(1010, vec![aircraft("4ca2d6", Some("7600"), false), aircraft("406b90", Some("7700"), false)]),
+                   vec![("406b90".to_string(), AlertKind::Emergency), ("4ca2d6".to_string(), AlertKind::RadioFailure)]);
+        assert_eq!(apply(1020, vec![aircraft("406b90", Some("7000"), false)]), vec![]);
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let actions = log.lines().map(|x| x.split(' ').nth(3).unwrap()).collect::<Vec<&str>>();
+        assert_eq!(actions, vec!["RAISED", "RAISED", "CLEARED", "LOST"]);
+        assert!(log.starts_with("[1970-01-01 00:16:40 UTC] RAISED 7600 Radio failure: BAW123 (4ca2d6) at 51.4700,-0.4543, 3000m"), "{}", log);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_alerts_kept_out_of_view() {
+        let mut store = AircraftStore::new();
+        let mut monitor = AlertMonitor::new(None).unwrap();
+        let mut apply = |time, data: Vec<Aircraft>, bounds: GeoBounds| {
+            let events = store.apply(AircraftData { time, data }, Some(&bounds));
+            monitor.process(&events, &store, UnitSystem::Metric);
+            monitor.active().iter().map(|x| (x.icao24.clone(), x.raised_at)).collect::<Vec<(String, isize)>>()
+        };
+
+        let heathrow = GeoBounds::new(51.0, -1.0, 52.0, 0.0);
+        let elsewhere = GeoBounds::new(40.0, 10.0, 41.0, 11.0);
+
+        assert_eq!(apply(1000, vec![aircraft("4ca2d6", Some("7700"), false)], heathrow), vec![("4ca2d6".to_string(), 1000)]);
+
+        // Panning away leaves the alert active, and panning back does not raise it again
+        assert_eq!(apply(1010, vec![], elsewhere), vec![("4ca2d6".to_string(), 1000)]);
+        assert_eq!(apply(1020, vec![aircraft("4ca2d6", Some("7700"), false)], heathrow), vec![("4ca2d6".to_string(), 1000)]);
+
+        // Whereas an aircraft which disappears from within the view is lost
+        assert_eq!(apply(1030, vec![], heathrow), vec![]);
+    }
+}
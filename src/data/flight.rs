@@ -1,20 +1,103 @@
 use serde::*;
+use serde_tuple::*;
 
+// Flight of a single aircraft, as returned by flight queries.  The airports are estimated from the track, and
+// any of them may be null if no airport could be identified, e.g. for flights still in progress
 #[derive(Debug, Clone, Deserialize)]
 pub struct Flight {
     #[serde(rename = "icao24")]                             pub icao24: String,
     #[serde(rename = "firstSeen")]                          pub first_seen: i64,
-    #[serde(rename = "estDepartureAirport")]                pub est_departure_airport: String,
+    #[serde(rename = "estDepartureAirport")]                pub est_departure_airport: Option<String>,
     #[serde(rename = "lastSeen")]                           pub last_seen: i64,
-    #[serde(rename = "estArrivalAirport")]                  pub est_arrival_airport: String,
-    #[serde(rename = "callsign")]                           pub callsign: String,
-    #[serde(rename = "estDepartureAirportHorizDistance")]   pub est_departure_airport_horiz_distance: i64,
-    #[serde(rename = "estDepartureAirportVertDistance")]    pub est_departure_airport_vert_distance: i64,
-    #[serde(rename = "estArrivalAirportHorizDistance")]     pub est_arrival_airport_horiz_distance: i64,
-    #[serde(rename = "estArrivalAirportVertDistance")]      pub est_arrival_airport_vert_distance: i64,
-    #[serde(rename = "departureAirportCandidatesCount")]    pub departure_airport_candidates_count: i64,
-    #[serde(rename = "arrivalAirportCandidatesCount")]      pub arrival_airport_candidates_count: i64
+    #[serde(rename = "estArrivalAirport")]                  pub est_arrival_airport: Option<String>,
+    #[serde(rename = "callsign")]                           pub callsign: Option<String>,
+    #[serde(rename = "estDepartureAirportHorizDistance")]   pub est_departure_airport_horiz_distance: Option<i64>,
+    #[serde(rename = "estDepartureAirportVertDistance")]    pub est_departure_airport_vert_distance: Option<i64>,
+    #[serde(rename = "estArrivalAirportHorizDistance")]     pub est_arrival_airport_horiz_distance: Option<i64>,
+    #[serde(rename = "estArrivalAirportVertDistance")]      pub est_arrival_airport_vert_distance: Option<i64>,
+    #[serde(rename = "departureAirportCandidatesCount", default)]  pub departure_airport_candidates_count: i64,
+    #[serde(rename = "arrivalAirportCandidatesCount", default)]    pub arrival_airport_candidates_count: i64
 }
 
 // Collection type returned in queries
 pub type FlightData = Vec<Flight>;
+
+// Trajectory of a single aircraft, as returned by track queries
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Track {
+    #[serde(rename = "icao24")]                             pub icao24: String,
+    #[serde(rename = "startTime")]                          pub start_time: i64,
+    #[serde(rename = "endTime")]                            pub end_time: i64,
+    #[serde(rename = "callsign")]                           pub callsign: Option<String>,
+    #[serde(rename = "path")]                               pub path: Vec<Waypoint>
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize_tuple)]
+pub struct Waypoint {
+    pub time: i64,                          // Time of this waypoint, as unix timestamp
+    pub latitude: Option<f64>,              // Can be null
+    pub longitude: Option<f64>,             // Can be null
+    pub baro_altitude: Option<f32>,         // Barometric altitude, meters.  Can be null
+    pub true_track: Option<f32>,            // Decimal degrees clockwise from N.  Can be null
+    pub on_ground: bool
+}
+
+impl Flight {
+    pub fn departure(&self) -> Option<&str> {
+        self.est_departure_airport.as_deref().map(str::trim).filter(|x| !x.is_empty())
+    }
+
+    pub fn arrival(&self) -> Option<&str> {
+        self.est_arrival_airport.as_deref().map(str::trim).filter(|x| !x.is_empty())
+    }
+
+    pub fn callsign(&self) -> Option<&str> {
+        self.callsign.as_deref().map(str::trim).filter(|x| !x.is_empty())
+    }
+}
+
+// Flights of the given aircraft, most recent first
+pub fn recent_flights<'a>(flights: &'a [Flight], icao24: &str) -> Vec<&'a Flight> {
+    let mut recent = flights.iter()
+        .filter(|x| x.icao24 == icao24)
+        .collect::<Vec<&Flight>>();
+
+    recent.sort_by_key(|x| std::cmp::Reverse(x.last_seen));
+    recent
+}
+
+impl Waypoint {
+    pub fn position(&self) -> Option<(f64, f64)> {
+        match (self.longitude, self.latitude) {
+            (Some(lon), Some(lat)) => Some((lon, lat)),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FlightData, recent_flights};
+
+    #[test]
+    fn test_missing_flight_fields() {
+        let json = r#"[
+            {"icao24":"4ca2d6","firstSeen":1566130000,"estDepartureAirport":"EGLL","lastSeen":1566137000,"estArrivalAirport":null,
+             "callsign":"BAW123  ","estDepartureAirportHorizDistance":1200,"estDepartureAirportVertDistance":50,
+             "estArrivalAirportHorizDistance":null,"estArrivalAirportVertDistance":null,
+             "departureAirportCandidatesCount":1,"arrivalAirportCandidatesCount":0},
+            {"icao24":"4ca2d6","firstSeen":1566100000,"estDepartureAirport":null,"lastSeen":1566110000,"estArrivalAirport":"  ",
+             "callsign":null},
+            {"icao24":"406b90","firstSeen":1566120000,"lastSeen":1566140000}]"#;
+        let flights: FlightData = serde_json::from_str(json).unwrap();
+
+        let recent = recent_flights(&flights, "4ca2d6");
+        assert_eq!(recent.iter().map(|x| x.last_seen).collect::<Vec<i64>>(), vec![1566137000, 1566110000]);
+
+        assert_eq!((recent[0].departure(), recent[0].arrival(), recent[0].callsign()), (Some("EGLL"), None, Some("BAW123")));
+        assert_eq!((recent[1].departure(), recent[1].arrival(), recent[1].callsign()), (None, None, None));
+        assert_eq!(recent[1].arrival_airport_candidates_count, 0);
+    }
+}
@@ -1,4 +1,13 @@
 pub mod parsing;
 pub mod aircraft;
 pub mod flight;
+pub mod store;
+pub mod trails;
+pub mod alerts;
+pub mod aircraft_database;
+pub mod search;
+pub mod icao_address;
+pub mod airlines;
+pub mod airports;
+pub mod airport_board;
 pub mod geography;
\ No newline at end of file
@@ -71,9 +71,40 @@ fn is_simple_multiline_string(data: &str) -> bool {
     data.starts_with(SIMPLE_MULTI_LINE_STRING_PREFIX)
 }
 
+// Splits a single CSV record into its fields.  Fields may be quoted with either double or single quotes, as
+// used by different versions of the OpenSky aircraft database, with quotes inside a field escaped by doubling
+pub fn parse_csv_record(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quote: Option<char> = None;
+    let mut at_field_start = true;
+
+    let mut chars = line.trim_end_matches(['\r', '\n']).chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => {
+                if chars.peek() == Some(&q) { field.push(q); chars.next(); }
+                else { quote = None; }
+            },
+            Some(_) => field.push(c),
+            None if c == ',' => {
+                fields.push(std::mem::take(&mut field));
+                at_field_start = true;
+                continue;
+            },
+            None if at_field_start && (c == '"' || c == '\'') => quote = Some(c),
+            None => field.push(c)
+        }
+        at_field_start = false;
+    }
+
+    fields.push(field);
+    fields
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{GeoShpIter, parse_simple_multiline_string};
+    use super::{GeoShpIter, parse_simple_multiline_string, parse_csv_record};
 
     fn run_iter<'a>(input: &'a String) -> Vec<&'a str> {
         GeoShpIter::new(input).collect::<Vec<&'a str>>()
@@ -111,6 +142,15 @@ mod tests {
         assert_eq!(parse_simple_multiline_string(&"MULTILINESTRING (())".to_string()),
                    vec![Vec::<&str>::new()]);
     }
+
+    #[test]
+    fn test_csv_record_parsing() {
+        assert_eq!(parse_csv_record("4ca2d6,G-EUPT,Airbus,A319 131,A319\r\n"), vec!["4ca2d6", "G-EUPT", "Airbus", "A319 131", "A319"]);
+        assert_eq!(parse_csv_record(r#""4ca2d6","Airbus Industrie, Toulouse","The ""Big"" One",,"#),
+                   vec!["4ca2d6", "Airbus Industrie, Toulouse", "The \"Big\" One", "", ""]);
+        assert_eq!(parse_csv_record("'a0b1c2','','O''Neill Air',O'Hare"), vec!["a0b1c2", "", "O'Neill Air", "O'Hare"]);
+        assert_eq!(parse_csv_record(""), vec![""]);
+    }
 }
 
 
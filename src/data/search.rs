@@ -0,0 +1,161 @@
+use std::collections::{HashMap, HashSet};
+use crate::data::aircraft::Aircraft;
+use crate::data::aircraft_database::AircraftDatabase;
+use crate::data::airlines::AirlineTable;
+
+// Free-text filter over the current aircraft, matching any part of the callsign, icao24, airline or database
+// details of each, e.g. "BAW", "BA12", "G-EU", "A320" or "Ryanair".  An empty query matches everything.  Results
+// are held until the next update, which is only required when the query or the aircraft change
+#[derive(Debug, Clone, Default)]
+pub struct AircraftSearch {
+    query: String,
+    pattern: String,                        // Normalised query used for matching
+    results: Vec<String>,                   // icao24 of all matching aircraft, ordered by callsign and then icao24
+    matching: HashSet<String>,              // The same, for lookup; empty if the query is inactive
+    airlines: HashMap<String, Option<String>>   // Lowercase airline flight of each callsign, e.g. "british airways ba123"
+}
+
+impl AircraftSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn query(&self) -> &str { &self.query }
+    pub fn is_active(&self) -> bool { !self.pattern.is_empty() }
+    pub fn results(&self) -> &[String] { &self.results }
+
+    pub fn push(&mut self, text: &str) {
+        self.query.extend(text.chars().filter(|c| !c.is_control()));
+        self.update_pattern();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.update_pattern();
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.update_pattern();
+    }
+
+    fn update_pattern(&mut self) {
+        self.pattern = self.query.trim().to_lowercase();
+    }
+
+    // Whether the aircraft matched the query at the last update
+    pub fn includes(&self, icao24: &str) -> bool {
+        !self.is_active() || self.matching.contains(icao24)
+    }
+
+    // Recomputes the results against the current query
+    pub fn update<'a>(&mut self, aircraft: impl Iterator<Item = &'a Aircraft>, database: &AircraftDatabase,
+                      airlines: &AirlineTable) {
+        let aircraft = aircraft.collect::<Vec<&Aircraft>>();
+        if self.is_active() {
+            self.update_airlines(&aircraft, airlines);
+        }
+
+        let mut results = aircraft.into_iter()
+            .filter(|x| self.matches(x, database))
+            .collect::<Vec<&Aircraft>>();
+
+        results.sort_by(|a, b| a.callsign.as_deref().map(str::trim).unwrap_or("~")
+            .cmp(b.callsign.as_deref().map(str::trim).unwrap_or("~"))
+            .then(a.icao24.cmp(&b.icao24)));
+
+        self.results = results.iter().map(|x| x.icao24.clone()).collect();
+        self.matching = if self.is_active() { self.results.iter().cloned().collect() } else { HashSet::new() };
+    }
+
+    // Resolves the airline of each callsign not already known, and discards those no longer in use
+    fn update_airlines(&mut self, aircraft: &[&Aircraft], airlines: &AirlineTable) {
+        let mut previous = std::mem::take(&mut self.airlines);
+        for callsign in aircraft.iter().filter_map(|x| x.callsign.as_deref().map(str::trim)) {
+            if !self.airlines.contains_key(callsign) {
+                let airline = previous.remove(callsign)
+                    .unwrap_or_else(|| airlines.resolve(callsign).map(|x| x.description().to_lowercase()));
+                self.airlines.insert(callsign.to_string(), airline);
+            }
+        }
+    }
+
+    fn matches(&self, aircraft: &Aircraft, database: &AircraftDatabase) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+
+        let callsign = aircraft.callsign.as_deref().map(str::trim);
+        let airline = callsign.and_then(|x| self.airlines.get(x)).and_then(|x| x.as_deref());
+
+        callsign.map(|x| contains_ignore_case(x, &self.pattern)).unwrap_or(false) ||
+            contains_ignore_case(&aircraft.icao24, &self.pattern) ||
+            airline.map(|x| x.contains(self.pattern.as_str())).unwrap_or(false) ||
+            database.get(&aircraft.icao24).map(|x| x.search_text().contains(self.pattern.as_str())).unwrap_or(false)
+    }
+}
+
+// Whether the text contains the (non-empty, lowercase) pattern, ignoring ASCII case, without allocating
+fn contains_ignore_case(text: &str, pattern: &str) -> bool {
+    text.as_bytes().windows(pattern.len()).any(|x| x.eq_ignore_ascii_case(pattern.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AircraftSearch;
+    use crate::data::aircraft::Aircraft;
+    use crate::data::aircraft_database::AircraftDatabase;
+    use crate::data::airlines::AirlineTable;
+
+    #[test]
+    fn test_search() {
+        let database = AircraftDatabase::parse("icao24,registration,manufacturername,model,typecode,operator\n\
+                                                4ca2d6,EI-DVM,Boeing,737-8AS,B738,Ryanair\n\
+                                                406b90,G-EUPT,Airbus,A319 131,A319,British Airways").unwrap();
+        let airlines = AirlineTable::parse("icao,iata,name\nBAW,BA,British Airways\nRYR,FR,Ryanair").unwrap();
+        let aircraft = [
+            Aircraft { callsign: Some("RYR4GP  ".to_string()), ..Aircraft::new("4ca2d6", 1000) },
+            Aircraft { callsign: Some("BAW123  ".to_string()), ..Aircraft::new("406b90", 1000) },
+            Aircraft { callsign: None, ..Aircraft::new("a0b1c2", 1000) }
+        ];
+        let results = |search: &mut AircraftSearch| {
+            search.update(aircraft.iter(), &database, &airlines);
+            search.results().to_vec()
+        };
+
+        let mut search = AircraftSearch::new();
+        assert!(!search.is_active());
+        assert_eq!(results(&mut search), vec!["406b90", "4ca2d6", "a0b1c2"]);
+        assert!(search.includes("a0b1c2"));
+
+        search.push("ryanair");
+        assert_eq!(results(&mut search), vec!["4ca2d6"]);
+        assert!(search.includes("4ca2d6") && !search.includes("406b90"));
+
+        search.clear();
+        search.push("g-eu\n");
+        assert_eq!(search.query(), "g-eu");
+        assert_eq!(results(&mut search), vec!["406b90"]);
+
+        search.clear();
+        search.push("rr");
+        assert_eq!(results(&mut search), Vec::<&str>::new());
+        search.pop();
+        assert_eq!(results(&mut search), vec!["406b90", "4ca2d6"]);
+
+        search.clear();
+        search.push("ba123");
+        assert_eq!(results(&mut search), vec!["406b90"]);
+
+        search.clear();
+        search.push("A0B1");
+        assert_eq!(results(&mut search), vec!["a0b1c2"]);
+
+        // Zero-padded flight numbers match the equivalent IATA flight number
+        let padded = [Aircraft { callsign: Some("BAW0456 ".to_string()), ..Aircraft::new("406b91", 1000) }];
+        search.clear();
+        search.push("ba456");
+        search.update(padded.iter(), &database, &airlines);
+        assert_eq!(search.results(), ["406b91"]);
+    }
+}
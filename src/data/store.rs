@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use crate::data::aircraft::{Aircraft, AircraftData};
+use crate::geo::bounds::GeoBounds;
+
+// Aircraft which drop out of a bounded snapshot because they are outside its bounds may well still be in flight, so
+// are retained until they have not been heard from for this long
+const STALE_AFTER_SECS: i64 = 10 * 60;
+
+// Change to a single aircraft, identified by icao24, resulting from a new snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub enum AircraftEvent {
+    Added(String),
+    Updated(String),
+    Lost(String)                            // No longer present in the data, e.g. out of range, but not merely out of view
+}
+
+// Current state of all aircraft, indexed by icao24
+pub struct AircraftStore {
+    time: isize,                            // Time of the most recent snapshot
+    aircraft: HashMap<String, Aircraft>
+}
+
+impl AircraftStore {
+    pub fn new() -> Self {
+        Self { time: 0, aircraft: HashMap::new() }
+    }
+
+    pub fn time(&self) -> isize { self.time }
+    pub fn len(&self) -> usize { self.aircraft.len() }
+
+    pub fn get(&self, icao24: &str) -> Option<&Aircraft> {
+        self.aircraft.get(icao24)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Aircraft> {
+        self.aircraft.values()
+    }
+
+    // Replaces the contents of the store with this snapshot, which covers the given bounds, or the entire globe if None,
+    // returning the changes it represents.  Aircraft whose state is identical to the previous snapshot are not reported,
+    // and those last seen outside the bounds are retained unchanged until they become stale
+    pub fn apply(&mut self, snapshot: AircraftData, bounds: Option<&GeoBounds>) -> Vec<AircraftEvent> {
+        let mut previous = std::mem::take(&mut self.aircraft);
+        let mut events = vec![];
+
+        self.time = snapshot.time;
+        for aircraft in snapshot.data {
+            match previous.remove(&aircraft.icao24) {
+                None => events.push(AircraftEvent::Added(aircraft.icao24.clone())),
+                Some(existing) if existing != aircraft => events.push(AircraftEvent::Updated(aircraft.icao24.clone())),
+                Some(_) => ()
+            }
+
+            self.aircraft.insert(aircraft.icao24.clone(), aircraft);
+        }
+
+        for (icao24, aircraft) in previous {
+            if is_out_of_view(&aircraft, bounds) && !is_stale(&aircraft, self.time) {
+                self.aircraft.insert(icao24, aircraft);
+            }
+            else {
+                events.push(AircraftEvent::Lost(icao24));
+            }
+        }
+
+        events
+    }
+}
+
+fn is_out_of_view(aircraft: &Aircraft, bounds: Option<&GeoBounds>) -> bool {
+    match (bounds, aircraft.longitude, aircraft.latitude) {
+        (Some(bounds), Some(lon), Some(lat)) => !bounds.contains(lon, lat),
+        _ => false
+    }
+}
+
+// Either side of the snapshot time, e.g. after seeking back in a replay
+fn is_stale(aircraft: &Aircraft, time: isize) -> bool {
+    (time as i64 - aircraft.last_contact).abs() > STALE_AFTER_SECS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AircraftStore, AircraftEvent, STALE_AFTER_SECS};
+    use crate::data::aircraft::{Aircraft, AircraftData};
+    use crate::geo::bounds::GeoBounds;
+
+    fn snapshot(time: isize, aircraft: Vec<Aircraft>) -> AircraftData {
+        AircraftData { time, data: aircraft }
+    }
+
+    #[test]
+    fn test_snapshot_events() {
+        let mut store = AircraftStore::new();
+
+        let events = store.apply(snapshot(1000, vec![Aircraft::new("4ca2d6", 1000), Aircraft::new("406b90", 1000)]), None);
+        assert_eq!(events.len(), 2);
+        assert!(events.contains(&AircraftEvent::Added("406b90".to_string())));
+        assert_eq!(store.len(), 2);
+
+        let events = store.apply(snapshot(1005, vec![Aircraft::new("4ca2d6", 1000), Aircraft::new("406b90", 1005), Aircraft::new("a0b1c2", 1005)]), None);
+        assert_eq!(events, vec![AircraftEvent::Updated("406b90".to_string()), AircraftEvent::Added("a0b1c2".to_string())]);
+
+        let events = store.apply(snapshot(1010, vec![Aircraft::new("a0b1c2", 1005)]), None);
+        assert_eq!(events.len(), 2);
+        assert!(events.contains(&AircraftEvent::Lost("4ca2d6".to_string())));
+        assert!(events.contains(&AircraftEvent::Lost("406b90".to_string())));
+
+        assert_eq!(store.time(), 1010);
+        assert_eq!(store.get("a0b1c2").unwrap().last_contact, 1005);
+        assert!(store.get("4ca2d6").is_none());
+    }
+
+    #[test]
+    fn test_aircraft_out_of_view_are_retained() {
+        let mut store = AircraftStore::new();
+        let at = |icao24, time, lon| Aircraft { longitude: Some(lon), latitude: Some(51.0), ..Aircraft::new(icao24, time) };
+        let west = GeoBounds::new(50.0, -2.0, 52.0, -1.0);
+        let east = GeoBounds::new(50.0, 1.0, 52.0, 2.0);
+
+        store.apply(snapshot(1000, vec![at("4ca2d6", 1000, -1.5), at("406b90", 1000, -1.2), Aircraft::new("a0b1c2", 1000)]), None);
+
+        // Panned east, leaving aircraft last seen in the west out of view.  Those without a position cannot be retained
+        let events = store.apply(snapshot(1010, vec![at("3c6444", 1010, 1.5)]), Some(&east));
+        assert_eq!(events, vec![AircraftEvent::Added("3c6444".to_string()), AircraftEvent::Lost("a0b1c2".to_string())]);
+        assert_eq!(store.get("4ca2d6").unwrap().last_contact, 1000);
+        assert_eq!(store.len(), 3);
+
+        // Panned back west, where one of them is no longer present
+        let events = store.apply(snapshot(1020, vec![at("4ca2d6", 1020, -1.45)]), Some(&west));
+        assert!(events.contains(&AircraftEvent::Updated("4ca2d6".to_string())));
+        assert!(events.contains(&AircraftEvent::Lost("406b90".to_string())));
+        assert!(store.get("3c6444").is_some());
+
+        // Aircraft out of view are eventually lost once stale
+        let time = 1020 + STALE_AFTER_SECS as isize + 1;
+        let events = store.apply(snapshot(time, vec![at("4ca2d6", time as i64, -1.4)]), Some(&west));
+        assert_eq!(events, vec![AircraftEvent::Updated("4ca2d6".to_string()), AircraftEvent::Lost("3c6444".to_string())]);
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::{HashMap, VecDeque};
+use crate::data::aircraft::Aircraft;
+use crate::geo::greatcircle;
+use crate::util::units::{Altitude, Speed};
+
+// Upper bound on the length of any trail, regardless of retention, so that memory use remains bounded
+pub const MAX_TRAIL_POINTS: usize = 1000;
+
+pub const DEFAULT_TRAIL_RETENTION: TrailRetention = TrailRetention::Minutes(10);
+
+// Jumps in position faster than this multiple of the reported ground speed (plus a fixed allowance for
+// receiver error) are treated as a discontinuity, e.g. after seeking in a replay, and restart the trail
+const MAX_PLAUSIBLE_SPEED_FACTOR: f64 = 3.0;
+const MAX_PLAUSIBLE_SPEED_ALLOWANCE: f64 = 150.0;       // m/s
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrailRetention {
+    Points(usize),                          // Most recent n positions
+    Minutes(u32)                            // All positions within this period of the most recent
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrailPoint {
+    pub time: i64,                          // Time of the position, as unix timestamp
+    pub longitude: f64,
+    pub latitude: f64,
+    pub altitude: Option<Altitude>,         // Barometric altitude where available, otherwise geometric
+    pub velocity: Option<Speed>             // Ground speed
+}
+
+// Recent positions of a single aircraft, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct Trail {
+    points: VecDeque<TrailPoint>
+}
+
+// Position history of all aircraft, indexed by icao24
+pub struct TrailStore {
+    retention: TrailRetention,
+    trails: HashMap<String, Trail>
+}
+
+impl TrailPoint {
+    pub fn from_aircraft(aircraft: &Aircraft) -> Option<Self> {
+        match (aircraft.longitude, aircraft.latitude) {
+            (Some(longitude), Some(latitude)) => Some(Self {
+                time: aircraft.time_position.unwrap_or(aircraft.last_contact),
+                longitude,
+                latitude,
+                altitude: aircraft.baro_altitude.or(aircraft.geo_altitude),
+                velocity: aircraft.velocity
+            }),
+            _ => None
+        }
+    }
+
+    pub fn position(&self) -> (f64, f64) {
+        (self.longitude, self.latitude)
+    }
+
+    // Determines whether the move from this point to the next is consistent with the reported speed
+    fn is_continuous_with(&self, next: &TrailPoint) -> bool {
+        let speed = next.velocity.or(self.velocity).map(|x| x.metres_per_sec()).unwrap_or(0.0) as f64;
+        let max_distance = (speed * MAX_PLAUSIBLE_SPEED_FACTOR + MAX_PLAUSIBLE_SPEED_ALLOWANCE) * (next.time - self.time) as f64;
+
+        greatcircle::distance(self.position(), next.position()) <= max_distance
+    }
+}
+
+impl Trail {
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize { self.points.len() }
+
+    pub fn points(&self) -> impl Iterator<Item = &TrailPoint> {
+        self.points.iter()
+    }
+
+    fn push(&mut self, point: TrailPoint, retention: TrailRetention) {
+        match self.points.back() {
+            Some(last) if last.time == point.time => return,
+            Some(last) if last.time > point.time || !last.is_continuous_with(&point) => self.points.clear(),
+            _ => ()
+        }
+
+        self.points.push_back(point);
+
+        let max_points = match retention {
+            TrailRetention::Points(n) => n.min(MAX_TRAIL_POINTS),
+            TrailRetention::Minutes(_) => MAX_TRAIL_POINTS
+        };
+        while self.points.len() > max_points {
+            self.points.pop_front();
+        }
+
+        if let (TrailRetention::Minutes(minutes), Some(latest)) = (retention, self.points.back().map(|x| x.time)) {
+            let earliest = latest - (minutes as i64 * 60);
+            while self.points.front().map(|x| x.time < earliest).unwrap_or(false) {
+                self.points.pop_front();
+            }
+        }
+    }
+}
+
+impl TrailStore {
+    pub fn new(retention: TrailRetention) -> Self {
+        Self { retention, trails: HashMap::new() }
+    }
+
+    pub fn get(&self, icao24: &str) -> Option<&Trail> {
+        self.trails.get(icao24)
+    }
+
+    // Adds the current position of this aircraft to its trail, if it has moved on since the last update
+    pub fn record(&mut self, aircraft: &Aircraft) {
+        if let Some(point) = TrailPoint::from_aircraft(aircraft) {
+            self.trails.entry(aircraft.icao24.clone())
+                .or_default()
+                .push(point, self.retention);
+        }
+    }
+
+    pub fn remove(&mut self, icao24: &str) {
+        self.trails.remove(icao24);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TrailStore, TrailRetention, TrailPoint, MAX_TRAIL_POINTS};
+    use crate::data::aircraft::{Aircraft, AircraftData};
+    use crate::data::store::{AircraftStore, AircraftEvent};
+    use crate::geo::bounds::GeoBounds;
+    use crate::util::units::Speed;
+
+    fn aircraft(time: i64, lon: f64, lat: f64) -> Aircraft {
+        Aircraft { time_position: Some(time), longitude: Some(lon), latitude: Some(lat), velocity: Some(Speed::from_metres_per_sec(250.0)),
+                   ..Aircraft::new("4ca2d6", time) }
+    }
+
+    fn times(store: &TrailStore) -> Vec<i64> {
+        store.get("4ca2d6").unwrap().points().map(|x| x.time).collect()
+    }
+
+    #[test]
+    fn test_retention() {
+        let mut by_points = TrailStore::new(TrailRetention::Points(3));
+        let mut by_minutes = TrailStore::new(TrailRetention::Minutes(1));
+
+        for i in 0..5 {
+            let position = aircraft(1000 + i * 20, -1.0 + i as f64 * 0.05, 51.0);
+            by_points.record(&position);
+            by_minutes.record(&position);
+        }
+
+        // Unchanged positions are not duplicated
+        by_points.record(&aircraft(1080, -0.8, 51.0));
+
+        assert_eq!(times(&by_points), vec![1040, 1060, 1080]);
+        assert_eq!(times(&by_minutes), vec![1020, 1040, 1060, 1080]);
+
+        let mut unbounded = TrailStore::new(TrailRetention::Points(usize::MAX));
+        for i in 0..(MAX_TRAIL_POINTS as i64 + 10) {
+            unbounded.record(&aircraft(i, -1.0, 51.0));
+        }
+        assert_eq!(unbounded.get("4ca2d6").unwrap().len(), MAX_TRAIL_POINTS);
+    }
+
+    #[test]
+    fn test_discontinuities_restart_trail() {
+        let mut store = TrailStore::new(TrailRetention::Points(10));
+        store.record(&aircraft(1000, -1.0, 51.0));
+        store.record(&aircraft(1010, -0.96, 51.0));
+
+        // ~280km in 10 seconds
+        store.record(&aircraft(1020, 3.0, 51.0));
+        assert_eq!(times(&store), vec![1020]);
+
+        // Earlier time, e.g. after seeking back in a replay
+        store.record(&aircraft(1010, 3.0, 51.0));
+        assert_eq!(times(&store), vec![1010]);
+
+        // Aircraft without a position are ignored
+        store.record(&Aircraft::new("406b90", 1000));
+        assert!(store.get("406b90").is_none());
+        assert!(TrailPoint::from_aircraft(&Aircraft::new("406b90", 1000)).is_none());
+    }
+
+    #[test]
+    fn test_trail_kept_out_of_view() {
+        let mut store = AircraftStore::new();
+        let mut trails = TrailStore::new(TrailRetention::Points(10));
+        let mut apply = |time, data: Vec<Aircraft>, bounds: GeoBounds| {
+            for event in store.apply(AircraftData { time, data }, Some(&bounds)) {
+                match event {
+                    AircraftEvent::Added(icao24) | AircraftEvent::Updated(icao24) => trails.record(store.get(&icao24).unwrap()),
+                    AircraftEvent::Lost(icao24) => trails.remove(&icao24)
+                }
+            }
+            trails.get("4ca2d6").map(|x| x.len())
+        };
+
+        let west = GeoBounds::new(50.0, -2.0, 52.0, 0.0);
+        let east = GeoBounds::new(50.0, 1.0, 52.0, 2.0);
+
+        assert_eq!(apply(1000, vec![aircraft(1000, -1.0, 51.0)], west), Some(1));
+        assert_eq!(apply(1010, vec![aircraft(1010, -0.96, 51.0)], west), Some(2));
+
+        // Panning away and back again continues the same trail
+        assert_eq!(apply(1020, vec![], east), Some(2));
+        assert_eq!(apply(1030, vec![aircraft(1030, -0.88, 51.0)], west), Some(3));
+
+        // Until the aircraft disappears from within the view
+        assert_eq!(apply(1040, vec![], west), None);
+    }
+}
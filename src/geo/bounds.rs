@@ -0,0 +1,91 @@
+use crate::geo::coords;
+
+const MIN_LONGITUDE: f64 = -180.0;
+const MAX_LONGITUDE: f64 = 180.0;
+const MIN_LATITUDE: f64 = -90.0;
+const MAX_LATITUDE: f64 = 90.0;
+
+const VIEW_BOUNDS_MARGIN: f64 = 0.05;     // Proportion of the view extent added on each side
+
+// Geographic bounding box, in decimal degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoBounds {
+    pub lat_min: f64,
+    pub lon_min: f64,
+    pub lat_max: f64,
+    pub lon_max: f64
+}
+
+impl GeoBounds {
+    // Both limits in each axis are clamped to the globe, so that a view panned entirely off the map results
+    // in an empty box at its edge, rather than one which is inverted
+    pub fn new(lat_min: f64, lon_min: f64, lat_max: f64, lon_max: f64) -> Self {
+        Self {
+            lat_min: lat_min.clamp(MIN_LATITUDE, MAX_LATITUDE),
+            lon_min: lon_min.clamp(MIN_LONGITUDE, MAX_LONGITUDE),
+            lat_max: lat_max.clamp(MIN_LATITUDE, MAX_LATITUDE),
+            lon_max: lon_max.clamp(MIN_LONGITUDE, MAX_LONGITUDE)
+        }
+    }
+
+    // Bounds of the area currently visible in the window, plus a small margin.  Returns None if
+    // the view covers the entire globe, in which case no bounded query is required
+    pub fn from_view(view_origin: &[f64; 2], zoom_level: f64) -> Option<Self> {
+        let (lon0, lat0) = coords::map_to_lon_lat(0.0 - VIEW_BOUNDS_MARGIN, 0.0 - VIEW_BOUNDS_MARGIN, view_origin, zoom_level);
+        let (lon1, lat1) = coords::map_to_lon_lat(1.0 + VIEW_BOUNDS_MARGIN, 1.0 + VIEW_BOUNDS_MARGIN, view_origin, zoom_level);
+
+        let bounds = Self::new(lat1.min(lat0), lon0.min(lon1), lat0.max(lat1), lon1.max(lon0));
+        if bounds.is_global() { None } else { Some(bounds) }
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.lat_min <= MIN_LATITUDE && self.lat_max >= MAX_LATITUDE &&
+        self.lon_min <= MIN_LONGITUDE && self.lon_max >= MAX_LONGITUDE
+    }
+
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        lon >= self.lon_min && lon <= self.lon_max && lat >= self.lat_min && lat <= self.lat_max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeoBounds;
+
+    #[test]
+    fn test_default_view_is_global() {
+        assert_eq!(GeoBounds::from_view(&[0.0, 0.0], 1.0), None);
+        assert_eq!(GeoBounds::from_view(&[-0.5, -0.5], 0.5), None);
+    }
+
+    #[test]
+    fn test_zoomed_view_bounds() {
+        // Zoomed x4 into the region spanning 90W-0E, 0N-45N, plus margin
+        let bounds = GeoBounds::from_view(&[0.25, 0.25], 4.0).unwrap();
+        let expected = GeoBounds::new(-2.25, -94.5, 47.25, 4.5);
+
+        assert!((bounds.lat_min - expected.lat_min).abs() < 1e-9);
+        assert!((bounds.lat_max - expected.lat_max).abs() < 1e-9);
+        assert!((bounds.lon_min - expected.lon_min).abs() < 1e-9);
+        assert!((bounds.lon_max - expected.lon_max).abs() < 1e-9);
+        assert!(bounds.contains(-67.5, 33.0));
+        assert!(!bounds.contains(10.0, 33.0));
+    }
+
+    #[test]
+    fn test_bounds_are_clamped() {
+        let bounds = GeoBounds::from_view(&[-0.1, -0.1], 2.0).unwrap();
+        assert_eq!(bounds.lat_max, 90.0);
+        assert_eq!(bounds.lon_min, -180.0);
+    }
+
+    #[test]
+    fn test_view_off_the_map_is_not_inverted() {
+        // Panned beyond the top-left, and the bottom-right, of the map
+        for origin in [[-2.0, -2.0], [1.5, 1.5]].iter() {
+            let bounds = GeoBounds::from_view(origin, 4.0).unwrap();
+            assert!(bounds.lat_min <= bounds.lat_max && bounds.lon_min <= bounds.lon_max, "{:?}", bounds);
+            assert!(!bounds.contains(0.0, 0.0));
+        }
+    }
+}
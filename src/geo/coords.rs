@@ -62,6 +62,19 @@ pub fn lon_lat_to_map(lon: f64, lat: f64, view_origin: &[f64; 2], zoom_level: f6
         view_origin, zoom_level)
 }
 
+// Inverse of `lon_lat_to_map`; returns the (lon, lat) at the given normalised window position
+pub fn map_to_lon_lat(x: f64, y: f64, view_origin: &[f64; 2], zoom_level: f64) -> (f64, f64) {
+    let (eq_x, eq_y) = (
+        (x / zoom_level) + view_origin[0],
+        (y / zoom_level) + view_origin[1]
+    );
+
+    (
+        (eq_x * 360.0) - 180.0,
+        90.0 - (eq_y * 180.0)
+    )
+}
+
 fn screen_coords_to_map(coord: (f64, f64), view_origin: &[f64; 2], zoom_level: f64) -> (f64, f64) {
     (
         (coord.0 - view_origin[0]) * zoom_level,
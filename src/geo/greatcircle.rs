@@ -0,0 +1,147 @@
+// Calculations on a spherical earth, which is accurate to within ~0.5% and sufficient for display purposes
+
+pub const EARTH_RADIUS_METRES: f64 = 6_371_000.0;
+
+// Great-circle distance in metres between two (lon, lat) positions, by the haversine formula
+pub fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lon1, lat1) = (from.0.to_radians(), from.1.to_radians());
+    let (lon2, lat2) = (to.0.to_radians(), to.1.to_radians());
+
+    let a = ((lat2 - lat1) * 0.5).sin().powi(2) + lat1.cos() * lat2.cos() * ((lon2 - lon1) * 0.5).sin().powi(2);
+    2.0 * EARTH_RADIUS_METRES * a.sqrt().min(1.0).asin()
+}
+
+// Position reached by travelling the given distance in metres from a (lon, lat) position, on an initial
+// bearing in degrees clockwise from N
+pub fn destination(from: (f64, f64), bearing: f64, distance: f64) -> (f64, f64) {
+    let (lon1, lat1) = (from.0.to_radians(), from.1.to_radians());
+    let (bearing, angle) = (bearing.to_radians(), distance / EARTH_RADIUS_METRES);
+
+    let lat2 = (lat1.sin() * angle.cos() + lat1.cos() * angle.sin() * bearing.cos()).asin();
+    let lon2 = lon1 + (bearing.sin() * angle.sin() * lat1.cos()).atan2(angle.cos() - lat1.sin() * lat2.sin());
+
+    (normalise_longitude(lon2.to_degrees()), lat2.to_degrees())
+}
+
+// Initial bearing in degrees clockwise from N, in the range [0, 360), of the great circle between two positions
+pub fn initial_bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lon1, lat1) = (from.0.to_radians(), from.1.to_radians());
+    let (lon2, lat2) = (to.0.to_radians(), to.1.to_radians());
+
+    let y = (lon2 - lon1).sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * (lon2 - lon1).cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+// Position at the given fraction of the way along the great circle between two positions.  The route between
+// coincident or antipodal positions is undefined, in which case the starting position is returned
+pub fn intermediate(from: (f64, f64), to: (f64, f64), fraction: f64) -> (f64, f64) {
+    let angle = distance(from, to) / EARTH_RADIUS_METRES;
+    if angle.sin().abs() < 1e-12 {
+        return from;
+    }
+
+    let (lon1, lat1) = (from.0.to_radians(), from.1.to_radians());
+    let (lon2, lat2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((1.0 - fraction) * angle).sin() / angle.sin();
+    let b = (fraction * angle).sin() / angle.sin();
+
+    let x = a * lat1.cos() * lon1.cos() + b * lat2.cos() * lon2.cos();
+    let y = a * lat1.cos() * lon1.sin() + b * lat2.cos() * lon2.sin();
+    let z = a * lat1.sin() + b * lat2.sin();
+
+    (normalise_longitude(y.atan2(x).to_degrees()), z.atan2((x * x + y * y).sqrt()).to_degrees())
+}
+
+// Positions along the great circle between two positions, including both, spaced no more than the given
+// distance in metres apart.  Straight lines between them then approximate the curve in any projection
+pub fn densify(from: (f64, f64), to: (f64, f64), max_spacing: f64) -> Vec<(f64, f64)> {
+    let segments = (distance(from, to) / max_spacing).ceil().max(1.0) as usize;
+
+    (0..=segments)
+        .map(|i| intermediate(from, to, i as f64 / segments as f64))
+        .collect()
+}
+
+// Distance in metres along the great circle from `from` towards `to`, to the point on it closest to the given
+// position.  Negative if that point is behind the starting position
+pub fn along_track_distance(from: (f64, f64), to: (f64, f64), position: (f64, f64)) -> f64 {
+    let angle = distance(from, position) / EARTH_RADIUS_METRES;
+    let relative_bearing = (initial_bearing(from, position) - initial_bearing(from, to)).to_radians();
+
+    let cross_track = (angle.sin() * relative_bearing.sin()).asin();
+    let along_track = (angle.cos() / cross_track.cos()).clamp(-1.0, 1.0).acos() * EARTH_RADIUS_METRES;
+
+    if relative_bearing.cos() < 0.0 { -along_track } else { along_track }
+}
+
+// Wraps a longitude into the range [-180, 180)
+pub fn normalise_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance, destination, initial_bearing, intermediate, densify, along_track_distance};
+
+    #[test]
+    fn test_distance() {
+        // London Heathrow to New York JFK, ~5540km on a spherical earth
+        let d = distance((-0.4543, 51.4700), (-73.7781, 40.6413));
+        assert!((d - 5_540_000.0).abs() < 5_000.0, "{}", d);
+
+        assert_eq!(distance((10.0, 20.0), (10.0, 20.0)), 0.0);
+        assert!((distance((179.5, 0.0), (-179.5, 0.0)) - 111_195.0).abs() < 10.0);
+    }
+
+    #[test]
+    fn test_destination() {
+        let start = (-0.4543, 51.4700);
+        let (lon, lat) = destination(start, 60.0, 100_000.0);
+        assert!((distance(start, (lon, lat)) - 100_000.0).abs() < 1.0);
+        assert!(lon > start.0 && lat > start.1);
+
+        // Due north along a meridian, and east across the antimeridian
+        let (lon, lat) = destination((10.0, 0.0), 0.0, 111_195.0);
+        assert!((lon - 10.0).abs() < 1e-9 && (lat - 1.0).abs() < 1e-4);
+        let (lon, lat) = destination((179.5, 0.0), 90.0, 111_195.0);
+        assert!((lon + 179.5).abs() < 1e-4 && lat.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_route_interpolation() {
+        let (lhr, jfk) = ((-0.4543, 51.4700), (-73.7781, 40.6413));
+        let total = distance(lhr, jfk);
+
+        // The great circle runs well north of both airports, and starts out heading WNW
+        let (lon, lat) = intermediate(lhr, jfk, 0.5);
+        assert!((distance(lhr, (lon, lat)) - total * 0.5).abs() < 1.0);
+        assert!(lat > 51.47 && lon < -30.0 && lon > -45.0, "{}, {}", lon, lat);
+        assert!((initial_bearing(lhr, jfk) - 288.0).abs() < 1.0);
+
+        let route = densify(lhr, jfk, 100_000.0);
+        assert_eq!(route.len(), 57);
+        assert_eq!((route[0], route[56]), (intermediate(lhr, jfk, 0.0), intermediate(lhr, jfk, 1.0)));
+        assert!(route.windows(2).all(|x| distance(x[0], x[1]) <= 100_000.0));
+        assert!((route[56].0 - jfk.0).abs() < 1e-9 && (route[56].1 - jfk.1).abs() < 1e-9);
+
+        // Across the antimeridian, and between coincident positions
+        assert!(densify((170.0, 0.0), (-170.0, 0.0), 1_000_000.0).iter().all(|x| x.0.abs() >= 170.0));
+        assert_eq!(densify(lhr, lhr, 1000.0), vec![lhr, lhr]);
+    }
+
+    #[test]
+    fn test_along_track_distance() {
+        let (lhr, jfk) = ((-0.4543, 51.4700), (-73.7781, 40.6413));
+        let total = distance(lhr, jfk);
+
+        // Points on, and offset from, the route
+        let on_route = intermediate(lhr, jfk, 0.25);
+        assert!((along_track_distance(lhr, jfk, on_route) - total * 0.25).abs() < 1.0);
+        let offset = destination(on_route, initial_bearing(on_route, jfk) + 90.0, 50_000.0);
+        assert!((along_track_distance(lhr, jfk, offset) - total * 0.25).abs() < 1_000.0);
+
+        assert!(along_track_distance(lhr, jfk, (10.0, 53.0)) < 0.0);
+        assert_eq!(along_track_distance(lhr, jfk, lhr), 0.0);
+    }
+}
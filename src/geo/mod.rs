@@ -1,2 +1,4 @@
 #![allow(dead_code)]
 pub mod coords;
+pub mod bounds;
+pub mod greatcircle;
@@ -1,3 +1,4 @@
+mod adsb;
 mod core;
 mod data;
 mod geo;
@@ -8,13 +9,27 @@ mod text;
 mod util;
 
 use crate::core::flight_radar;
+use crate::core::arguments::Arguments;
 use shader_version::OpenGL;
 
 fn main() {
+    let args = Arguments::from_env();
+
     let mut flight_radar = flight_radar::FlightRadar::create(
         flight_radar::BuildOptions {
             gl_version: OpenGL::V4_5,
-            use_cache: false
+            use_cache: args.flag("--use-cache"),
+            state_sources: args.state_sources(),
+            include_opensky: args.flag("--opensky"),
+            record_path: args.value("--record"),
+            playback_speed: args.playback_speed(),
+            trail_retention: args.trail_retention(),
+            max_extrapolation: args.max_extrapolation(),
+            alert_log: args.value("--alert-log"),
+            units: args.units(),
+            aircraft_database: args.value("--aircraft-db"),
+            airports: args.value("--airports"),
+            runways: args.value("--runways")
         }
     );
 
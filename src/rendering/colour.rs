@@ -7,7 +7,23 @@ pub const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
 
 pub const COLOUR_AIRCRAFT: Rgba<u8> = GREEN;
 pub const COLOUR_SELECTION: [f32; 4] = [152.0/255.0, 250.0/255.0, 161.0/255.0, 0.5];
+pub const COLOUR_TRACK: [f32; 4] = [1.0, 235.0/255.0, 133.0/255.0, 0.6];
 pub const COLOUR_SELECTED_OBJECT: [f32; 4] = [255.0/255.0, 235.0/255.0, 133.0/255.0, 0.5];
 pub const COLOUR_STATUS_AREA_BACK: [f32; 4] = [0.0/255.0, 0.0/255.0, 0.0/255.0, 1.0];
 pub const COLOUR_STATUS_AREA_OUTLINE: [f32; 4] = [31.0/255.0, 102.0/255.0, 50.0/255.0, 0.75];
 pub const COLOUR_STATUS_AREA_TEXT: [f32; 4] = [126.0/255.0, 214.0/255.0, 135.0/255.0, 1.0];
+pub const COLOUR_STATUS_AREA_WARNING: [f32; 4] = [1.0, 180.0/255.0, 80.0/255.0, 1.0];
+pub const COLOUR_TIMELINE_BACK: [f32; 4] = [31.0/255.0, 102.0/255.0, 50.0/255.0, 0.5];
+pub const COLOUR_TIMELINE_PROGRESS: [f32; 4] = [126.0/255.0, 214.0/255.0, 135.0/255.0, 0.9];
+pub const COLOUR_TRAIL_LOW: Rgba<u8> = Rgba([255, 196, 64, 255]);
+pub const COLOUR_TRAIL_HIGH: Rgba<u8> = Rgba([64, 160, 255, 255]);
+pub const COLOUR_ALERT: [f32; 4] = [1.0, 64.0/255.0, 64.0/255.0, 0.9];
+pub const COLOUR_ALERT_PANEL_BACK: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+pub const COLOUR_SEARCH_PANEL_BACK: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+pub const COLOUR_AIRPORT: [f32; 4] = [180.0/255.0, 180.0/255.0, 220.0/255.0, 0.8];
+pub const COLOUR_AIRPORT_LABEL: [f32; 4] = [180.0/255.0, 180.0/255.0, 220.0/255.0, 0.6];
+pub const COLOUR_ROUTE_AIRPORT: [f32; 4] = [1.0, 235.0/255.0, 133.0/255.0, 0.9];
+pub const COLOUR_ROUTE_FLOWN: [f32; 4] = [1.0, 235.0/255.0, 133.0/255.0, 0.25];
+pub const COLOUR_ROUTE_REMAINING: [f32; 4] = [1.0, 235.0/255.0, 133.0/255.0, 0.7];
+pub const COLOUR_FLIGHT_PANEL_BACK: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+pub const COLOUR_BOARD_PANEL_BACK: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
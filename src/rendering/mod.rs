@@ -2,9 +2,13 @@
 pub mod screenshot;
 
 use ::image;
-use crate::data::aircraft::{Aircraft, AircraftData};
+use crate::data::aircraft::{Aircraft, EstimatedPosition, Extrapolation};
+use crate::data::airports::{Airport, AirportDatabase, AirportType};
+use crate::data::trails::{Trail, TrailStore};
+use crate::data::flight::Track;
 use crate::data::geography::{GeoData, CoastlineDataEntry};
-use crate::geo::coords;
+use crate::geo::{coords, greatcircle};
+use crate::util::units::Altitude;
 use piston_window::*;
 use image::Rgba;
 
@@ -14,17 +18,30 @@ const COLOUR_COASTLINE: [f32; 4] = [140.0/255.0, 184.0/255.0, 151.0/255.0, 0.5];
 const COLOUR_AIRCRAFT: Rgba<u8> = colour::GREEN;
 
 const COASTLINE_WIDTH: f64 = 0.0005;
+const TRACK_WIDTH: f64 = 0.001;
+const ROUTE_WIDTH: f64 = 0.0008;
+const ROUTE_POINT_SPACING: f64 = 50_000.0;      // Meters between the points approximating each great-circle route
 
-pub fn prepare_backbuffer(buffer: &mut BackBuffer, draw_size: &[u32; 2], zoom_level: f64, view_origin: [f64; 2], aircraft: &AircraftData) {
+const ROUTE_AIRPORT_RADIUS: f64 = 6.0;          // Pixels
+
+const TRAIL_MIN_ALPHA: f64 = 0.1;               // Oldest point of each trail; the newest is fully opaque
+const TRAIL_ALTITUDE_RANGE: f32 = 12000.0;      // Trails are coloured by altitude, from ground level up to this altitude in meters
+
+// Renders the given aircraft at their extrapolated positions, returning the number within the view
+pub fn prepare_backbuffer(buffer: &mut BackBuffer, draw_size: &[u32; 2], zoom_level: f64, view_origin: [f64; 2],
+                          aircraft: &[&Aircraft], trails: &TrailStore, extrapolation: &Extrapolation) -> usize {
     clear_backbuffer(buffer);
 
+    // Render trails, behind all aircraft, joined up to the current position of each
+    aircraft.iter()
+        .filter_map(|x| trails.get(&x.icao24).map(|trail| (trail, extrapolation.position_of(x))))
+        .for_each(|(trail, current)| render_trail(trail, current, buffer, draw_size, zoom_level, &view_origin));
+
     // Render aircraft
-    let aircraft_rendered = aircraft.data.iter()
-        .map(|x| render_aircraft(x, buffer, draw_size, zoom_level, &view_origin))
+    aircraft.iter()
+        .map(|x| render_aircraft(x, extrapolation, buffer, draw_size, zoom_level, &view_origin))
         .filter(|&x| x)
-        .count();
-
-    println!("Processed: {}, Rendered: {}", aircraft.data.len(), aircraft_rendered);
+        .count()
 }
 
 pub fn perform_rendering(g: &mut G2d, context: &Context, render_size: (f64, f64), zoom_level: f64, view_origin: [f64; 2], geo_data: &GeoData) {
@@ -37,13 +54,95 @@ pub fn perform_rendering(g: &mut G2d, context: &Context, render_size: (f64, f64)
         .sum::<usize>();
 }
 
+// Renders a marker for each airport within the view, sized by the type of airport and omitting those too small
+// to be shown at this zoom level.  Returns the number rendered
+pub fn render_airports(g: &mut G2d, context: &Context, draw_size: &[f64; 2], zoom_level: f64, view_origin: &[f64; 2],
+                       airports: &AirportDatabase) -> usize {
+    airports.visible_at(zoom_level)
+        .map(|x| (x, coords::lon_lat_to_map(x.longitude, x.latitude, view_origin, zoom_level)))
+        .filter(|(_, pos)| coords::in_bounds(*pos))
+        .map(|(airport, (x, y))| {
+            let size = coords::normalise_to_window(airport_marker_size(airport), airport_marker_size(airport), draw_size);
+            rectangle(colour::COLOUR_AIRPORT, [x - size.0 * 0.5, y - size.1 * 0.5, size.0, size.1], context.transform, g);
+        })
+        .count()
+}
+
+// Highlights the departure and arrival airports of the selected flight
+pub fn render_route_airports(g: &mut G2d, context: &Context, draw_size: &[f64; 2], zoom_level: f64, view_origin: &[f64; 2],
+                             airports: &[&Airport]) {
+    let adj = coords::normalise_to_window(ROUTE_AIRPORT_RADIUS, ROUTE_AIRPORT_RADIUS, draw_size);
+    let highlight = Ellipse::new_border(colour::COLOUR_ROUTE_AIRPORT, adj.0 * 0.2);
+
+    airports.iter()
+        .map(|x| coords::lon_lat_to_map(x.longitude, x.latitude, view_origin, zoom_level))
+        .for_each(|(x, y)| highlight.draw_from_to([x - adj.0, y - adj.1], [x + adj.0, y + adj.1], &context.draw_state, context.transform, g));
+}
+
+// Renders the great-circle route between the departure and arrival (lon, lat) positions.  The portion already flown,
+// up to the point on the route closest to the current position of the aircraft, is drawn more faintly than the
+// portion remaining
+pub fn render_route(g: &mut G2d, context: &Context, zoom_level: f64, view_origin: &[f64; 2],
+                    departure: (f64, f64), arrival: (f64, f64), current_position: Option<(f64, f64)>) -> usize {
+    let total = greatcircle::distance(departure, arrival);
+    let flown = current_position
+        .map(|x| greatcircle::along_track_distance(departure, arrival, x))
+        .filter(|_| total > 0.0)
+        .map(|x| (x / total).clamp(0.0, 1.0))
+        .unwrap_or(0.0);
+    let split = greatcircle::intermediate(departure, arrival, flown);
+
+    render_geodesic(g, context, zoom_level, view_origin, departure, split, colour::COLOUR_ROUTE_FLOWN) +
+        render_geodesic(g, context, zoom_level, view_origin, split, arrival, colour::COLOUR_ROUTE_REMAINING)
+}
+
+// Renders the great circle between two (lon, lat) positions as a polyline, which follows its curve in the
+// equirectangular projection.  Returns the number of segments rendered
+fn render_geodesic(g: &mut G2d, context: &Context, zoom_level: f64, view_origin: &[f64; 2],
+                   from: (f64, f64), to: (f64, f64), colour: [f32; 4]) -> usize {
+    greatcircle::densify(from, to, ROUTE_POINT_SPACING)
+        .windows(2)
+        .filter(|seg| (seg[0].0 - seg[1].0).abs() <= 180.0)        // Omit the segment which wraps around the antimeridian
+        .map(|seg| (coords::lon_lat_to_map(seg[0].0, seg[0].1, view_origin, zoom_level),
+                    coords::lon_lat_to_map(seg[1].0, seg[1].1, view_origin, zoom_level)))
+        .map(|(v0, v1)| ([v0.0, v0.1], [v1.0, v1.1]))
+        .filter(|(v0, v1)| segment_in_bounds(*v0, *v1))
+        .map(|(v0, v1)| line_from_to(colour, ROUTE_WIDTH, v0, v1, context.transform, g))
+        .count()
+}
+
+fn airport_marker_size(airport: &Airport) -> f64 {
+    match airport.airport_type {
+        AirportType::Large => 4.0,
+        AirportType::Medium => 3.0,
+        _ => 2.0
+    }
+}
+
+// Renders the track as a polyline, optionally joined up to the current position of the aircraft
+pub fn render_track(g: &mut G2d, context: &Context, zoom_level: f64, view_origin: &[f64; 2],
+                    track: &Track, current_position: Option<(f64, f64)>) -> usize {
+    let transformed = track.path.iter()
+        .filter_map(|wp| wp.position())
+        .chain(current_position)
+        .map(|(lon, lat)| coords::lon_lat_to_map(lon, lat, view_origin, zoom_level))
+        .map(|v| [v.0, v.1])
+        .collect::<Vec<[f64; 2]>>();
+
+    transformed.windows(2)
+        .filter(|seg| segment_in_bounds(seg[0], seg[1]))
+        .map(|seg| line_from_to(colour::COLOUR_TRACK, TRACK_WIDTH, seg[0], seg[1], context.transform, g))
+        .count()
+}
+
 fn clear_backbuffer(canvas: &mut BackBuffer) {
     canvas.pixels_mut().for_each(|mut p| p.0 = [0, 0, 0, 0]);
 }
 
-fn render_aircraft(aircraft: &Aircraft, buffer: &mut BackBuffer, view_size: &[u32; 2], zoom_level: f64, view_origin: &[f64; 2]) -> bool {
-    if let (Some(lon), Some(lat)) = (aircraft.longitude, aircraft.latitude) {
-        let (x_norm_scaled, y_norm_scaled) = coords::lon_lat_to_map(lon, lat, view_origin, zoom_level);
+fn render_aircraft(aircraft: &Aircraft, extrapolation: &Extrapolation, buffer: &mut BackBuffer,
+                   view_size: &[u32; 2], zoom_level: f64, view_origin: &[f64; 2]) -> bool {
+    if let Some(position) = extrapolation.position_of(aircraft) {
+        let (x_norm_scaled, y_norm_scaled) = coords::lon_lat_to_map(position.longitude, position.latitude, view_origin, zoom_level);
 
         if coords::in_bounds((x_norm_scaled, y_norm_scaled)) {
             let (x, y) = ((x_norm_scaled * view_size[0] as f64) as u32, (y_norm_scaled * view_size[1] as f64) as u32);
@@ -55,6 +154,61 @@ fn render_aircraft(aircraft: &Aircraft, buffer: &mut BackBuffer, view_size: &[u3
     false
 }
 
+// Renders the trail as a polyline which fades out towards its oldest point, optionally joined up to the
+// current position of the aircraft
+fn render_trail(trail: &Trail, current_position: Option<EstimatedPosition>, buffer: &mut BackBuffer,
+                view_size: &[u32; 2], zoom_level: f64, view_origin: &[f64; 2]) {
+    let transformed = trail.points()
+        .map(|p| (p.position(), p.altitude))
+        .chain(current_position.map(|p| ((p.longitude, p.latitude), p.altitude)))
+        .map(|((lon, lat), alt)| (coords::lon_lat_to_map(lon, lat, view_origin, zoom_level), alt))
+        .map(|((x, y), alt)| ([x, y], alt))
+        .collect::<Vec<([f64; 2], Option<Altitude>)>>();
+
+    let segments = transformed.len().saturating_sub(1).max(1) as f64;
+    transformed.windows(2)
+        .enumerate()
+        .filter(|(_, seg)| segment_in_bounds(seg[0].0, seg[1].0))
+        .for_each(|(i, seg)| {
+            let alpha = TRAIL_MIN_ALPHA + (1.0 - TRAIL_MIN_ALPHA) * ((i + 1) as f64 / segments);
+            let colour = with_alpha(trail_colour(seg[1].1), alpha);
+            draw_line(buffer, view_size, seg[0].0, seg[1].0, colour);
+        });
+}
+
+fn trail_colour(altitude: Option<Altitude>) -> Rgba<u8> {
+    match altitude {
+        Some(alt) => {
+            let t = (alt.metres() / TRAIL_ALTITUDE_RANGE).clamp(0.0, 1.0);
+            let (low, high) = (colour::COLOUR_TRAIL_LOW.0, colour::COLOUR_TRAIL_HIGH.0);
+            Rgba([0, 1, 2, 3].map(|c| (low[c] as f32 + (high[c] as f32 - low[c] as f32) * t) as u8))
+        },
+        None => COLOUR_AIRCRAFT
+    }
+}
+
+fn with_alpha(colour: Rgba<u8>, alpha: f64) -> Rgba<u8> {
+    let [r, g, b, a] = colour.0;
+    Rgba([r, g, b, (a as f64 * alpha) as u8])
+}
+
+// Plots a line between two normalised map coordinates, clipped to the buffer
+fn draw_line(buffer: &mut BackBuffer, view_size: &[u32; 2], v0: [f64; 2], v1: [f64; 2], colour: Rgba<u8>) {
+    let (x0, y0) = (v0[0] * view_size[0] as f64, v0[1] * view_size[1] as f64);
+    let (x1, y1) = (v1[0] * view_size[0] as f64, v1[1] * view_size[1] as f64);
+
+    let max_steps = 2 * (view_size[0] + view_size[1]) as usize;       // Segments extending far off-screen are drawn more coarsely
+    let steps = ((x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize).min(max_steps);
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        let (x, y) = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+
+        if x >= 0.0 && y >= 0.0 && (x as u32) < buffer.width() && (y as u32) < buffer.height() {
+            buffer.put_pixel(x as u32, y as u32, colour);
+        }
+    }
+}
+
 fn render_coastline(data: &CoastlineDataEntry, g: &mut piston_window::G2d, context: &Context,
                     _render_size: (f64, f64), zoom_level: f64, view_origin: &[f64; 2]) -> usize {
     let transformed = data.vertices.iter()
@@ -0,0 +1,85 @@
+use std::time::Instant;
+
+// Simulation time, decoupled from wall time so that it can be paused, accelerated and repositioned
+#[derive(Debug, Clone)]
+pub struct SimulationClock {
+    base_time: f64,                         // Simulation time at the last change of rate or position
+    base_instant: Instant,                  // Wall time at which base_time applied
+    speed: f64,
+    paused: bool
+}
+
+impl SimulationClock {
+    pub fn new(time: f64, speed: f64) -> Self {
+        Self { base_time: time, base_instant: Instant::now(), speed, paused: false }
+    }
+
+    // Current simulation time, as unix timestamp
+    pub fn now(&self) -> f64 {
+        if self.paused {
+            self.base_time
+        } else {
+            self.base_time + self.base_instant.elapsed().as_secs_f64() * self.speed
+        }
+    }
+
+    pub fn is_paused(&self) -> bool { self.paused }
+    pub fn speed(&self) -> f64 { self.speed }
+
+    pub fn pause(&mut self) {
+        self.rebase();
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.rebase();
+        self.paused = false;
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.rebase();
+        self.speed = speed;
+    }
+
+    pub fn seek(&mut self, time: f64) {
+        self.base_time = time;
+        self.base_instant = Instant::now();
+    }
+
+    // Fixes the current time as the new base, so that subsequent changes apply only from now
+    fn rebase(&mut self) {
+        self.seek(self.now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimulationClock;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_pause_and_seek() {
+        let mut clock = SimulationClock::new(1000.0, 1.0);
+        clock.pause();
+        let paused_at = clock.now();
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(clock.now(), paused_at);
+
+        clock.seek(2000.0);
+        assert_eq!(clock.now(), 2000.0);
+        clock.resume();
+        assert!(clock.now() >= 2000.0);
+    }
+
+    #[test]
+    fn test_accelerated_clock() {
+        let mut clock = SimulationClock::new(1000.0, 1.0);
+        clock.set_speed(60.0);
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(clock.now() >= 1006.0);
+        assert!(clock.now() < 1010.0);
+    }
+}
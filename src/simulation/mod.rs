@@ -1,121 +1,358 @@
-use std::sync::mpsc::{Sender, Receiver, SendError};
-use std::thread;
-use std::time::Duration;
+use std::sync::mpsc::{Sender, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
 use crate::data::aircraft::AircraftData;
-use crate::data::flight::FlightData;
-use crate::sources::{sources, httpclient, caching};
+use crate::data::flight::{FlightData, Track};
+use crate::data::airport_board::AirportBoard;
+use serde::de::DeserializeOwned;
+use crate::sources::sources;
+use crate::sources::caching::{self, ResponseCache};
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError, Timeline};
+use crate::sources::httpclient::{HttpClient, HttpError};
+use crate::geo::bounds::GeoBounds;
+use crate::util::temporal::get_current_timestamp_secs;
+use clock::SimulationClock;
 
-pub enum RetrievalError {
-    HttpRequestError(reqwest::Error),
-    JsonParsingError(serde_json::error::Error),
-    FlightChannelSendError(SendError<FlightData>)
-}
+pub mod clock;
 
+// Shortest interval between periodic triggers, however fast playback is running
+const MIN_TRIGGER_INTERVAL_MS: u64 = 250;
 
-pub fn simulate(trigger: Receiver<sources::Source>, out: Sender<AircraftData>) {
-    let mut cache = caching::FilesystemCache::new(|x| x.clone());
-    loop {
-        trigger.recv()
-            .and_then(|source| {
-                println!("Simulating...");
-
-                let data = if source.should_use_cache() {
-                    let key = cache.cache_key(&source.get_underlying_path());
-                    cache.get(&key)
-                        .map(|data| Some(data))
-                        .unwrap_or_else(|e| {
-                            eprintln!("Cannot retrieve \"{}\" from cache: {}", key, e.to_string());
-                            None
-                        })
-                } else {
-                    httpclient::get(source.get_path().as_str())
-                        .map(|data| Some(data))
-                        .unwrap_or_else(|e| {
-                            eprintln!("Remote retrieval error: {}", e.to_string());
-                            None
-                        })
-                };
+// Hold-off applied when a source is rate-limited without saying for how long
+const DEFAULT_RATE_LIMIT_DELAY_SECS: u64 = 60;
 
-                if let Some(data) = data {
-                    match out.send(parse_data(data)) {
-                        Err(e) => eprintln!("Send error: {}", e.to_string()),
-                        _ => ()
-                    }
-                }
-                Ok(())
-            })
-            .expect("Failed to receive new data for simulation");
-    }
+// Below this many remaining requests, slow to one retrieval per interval so that the quota lasts
+pub const LOW_RATE_LIMIT_REMAINING: u32 = 50;
+const LOW_RATE_LIMIT_INTERVAL_SECS: u64 = 15;
+
+// The airport board lists flights over this window, centred on the simulation time and refreshed at this interval.
+// Flight data is only updated periodically by the source, so there is little to gain from refreshing more often
+const AIRPORT_BOARD_WINDOW_SECS: i64 = 24 * 60 * 60;
+const AIRPORT_BOARD_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+pub enum FlightDataRequest {
+    Flights(String, sources::Source),       // Recent flights for the given icao24
+    Track(String, sources::Source),         // Track of the given icao24
+    AirportBoard(Option<String>, SimulationClock)   // Periodically retrieve the board of the given airport around the
+                                                    // time of the simulation clock, or stop if None
 }
 
-pub fn periodic_trigger(trigger: Sender<sources::Source>, request: sources::Source, interval: u64) {
-    loop {
-        thread::sleep(Duration::from_secs(interval));
-        trigger
-            .send(request.clone())
-            .expect("Failed to trigger periodic simulation");
-    };
+pub enum FlightDataResponse {
+    Flights(FlightData),
+    Track(Track),
+    AirportBoard(String, Option<AirportBoard>)      // Board of the given airport, or None if it could not be retrieved
 }
 
-pub fn retrieve_flight_data(request: Receiver<(String, sources::Source)>, out: Sender<FlightData>) {
-    loop {
-        request.recv()
-            .map_or_else(
-                |e| println!("Failed to receive flight data ({:?})", e),
-                |(icao24, source)| {
-                    println!("Retrieving flight data for \"{}\"...", icao24);
-                    perform_flight_data_lookup(source)
-                        .map(|x| out.send(x).unwrap_or_else(|e| {
-                            println!("Failed to return flight data for \"{}\" to simulation ({})", icao24, e.to_string())
-                        }))
-                        .and_then(|_| Some(println!("Retrieved flight data successfully")));
-            });
-    }
+pub enum SimulationTrigger {
+    Refresh,                                // Retrieve new data for the current view
+    UpdateView(Option<GeoBounds>),          // View has changed; retrieve new data for the given bounds
+    Playback(PlaybackCommand)               // Adjust the simulation clock
 }
 
-fn perform_flight_data_lookup(source: sources::Source) -> Option<FlightData> {
-    let data = httpclient::get(source.get_path().as_str());
-    println!("SOURCE: {:?}, RESULT: {:?}", source, data);
-    data.map_or_else(|_| None, |x| serde_json::from_str::<FlightData>(x.as_str())
-        .map_or_else(|_| None, |x| Some(x)))
+pub enum PlaybackCommand {
+    TogglePause,
+    SetSpeed(f64),                          // Playback rate relative to wall time; recorded sources only
+    Step(isize),                            // Move by this many snapshots and pause; recorded sources only
+    Seek(isize)                             // Move to the given time; recorded sources only
 }
 
+// Adjusts the cadence of the periodic trigger
+pub enum TriggerControl {
+    Pause,
+    Resume,
+    SetRate(f64),                           // Trigger this many times more often than the base interval
+    Throttle(Duration)                      // Hold off the next trigger for at least this long
+}
 
-fn parse_data(data: String) -> AircraftData {
-    serde_json::from_str(data.as_str())
-        .unwrap_or_else(|e| panic!("Failed to deserialise response: {:?}", e))
+// Playback and source state reported back to the event loop after each simulation cycle
+#[derive(Debug, Clone)]
+pub struct SimulationStatus {
+    pub paused: bool,
+    pub speed: f64,
+    pub timeline: Option<(isize, isize)>,   // Extent of the navigable timeline, for recorded sources
+    pub source_error: Option<String>,       // Failure of the most recent retrieval, if any
+    pub requests_remaining: Option<u32>     // Request quota remaining at the source, if it reports one
 }
 
+impl SimulationStatus {
+    pub fn live() -> Self {
+        Self { paused: false, speed: 1.0, timeline: None, source_error: None, requests_remaining: None }
+    }
+}
 
 
-impl From<reqwest::Error> for RetrievalError {
-    fn from(error: reqwest::Error) -> RetrievalError {
-        RetrievalError::HttpRequestError(error)
+pub fn simulate(mut source: Box<dyn StateVectorSource>, speed: f64, trigger: Receiver<SimulationTrigger>,
+                control: Sender<TriggerControl>, out: Sender<(AircraftData, Option<GeoBounds>)>, status: Sender<SimulationStatus>) {
+    // Recorded sessions play from their start; live sources always run in real time
+    let timeline = source.timeline();
+    let mut clock = match &timeline {
+        Some(x) => SimulationClock::new(x.start() as f64, speed),
+        None => SimulationClock::new(get_current_timestamp_secs() as f64, 1.0)
+    };
+    if let Some(x) = &timeline {
+        control.send(TriggerControl::SetRate(speed)).unwrap_or_else(|e| eprintln!("Send error: {}", e));
+        println!("Replaying {}s of recorded data at {}x", x.end() - x.start(), speed);
     }
-}
-impl From<serde_json::error::Error> for RetrievalError {
-    fn from(error: serde_json::error::Error) -> RetrievalError {
-        RetrievalError::JsonParsingError(error)
+
+    let mut query = StateVectorQuery::default();
+    let mut source_error = None;
+    let mut throttled_until = Instant::now();
+    loop {
+        trigger.recv()
+            .and_then(|first| {
+                // Coalesce any requests which queued up during the last retrieval, e.g. while zooming
+                std::iter::once(first)
+                    .chain(trigger.try_iter())
+                    .for_each(|request| match request {
+                        SimulationTrigger::UpdateView(bounds) => query.bounds = bounds,
+                        SimulationTrigger::Refresh => (),
+                        SimulationTrigger::Playback(command) => apply_playback_command(&mut clock, timeline.as_ref(), &control, command)
+                    });
+
+                // Playback stops at the end of a recorded session
+                if let Some(x) = &timeline {
+                    if !clock.is_paused() && clock.now() >= x.end() as f64 {
+                        clock.seek(x.end() as f64);
+                        apply_playback_command(&mut clock, timeline.as_ref(), &control, PlaybackCommand::TogglePause);
+                    }
+                }
+
+                // A paused live feed holds the last snapshot; recorded data can still be re-read at the current time.
+                // View changes are also held back while the source is refusing requests
+                if (!clock.is_paused() || timeline.is_some()) && Instant::now() >= throttled_until {
+                    println!("Simulating...");
+                    query.time = timeline.as_ref().map(|_| clock.now() as isize);
+
+                    let result = source.fetch(&query);
+                    let throttle = match &result {
+                        Err(SourceError::RateLimited(delay)) => Some(delay.unwrap_or_else(|| Duration::from_secs(DEFAULT_RATE_LIMIT_DELAY_SECS))),
+                        _ => source.rate_limit()
+                            .and_then(|x| x.remaining)
+                            .filter(|&remaining| remaining < LOW_RATE_LIMIT_REMAINING)
+                            .map(|_| Duration::from_secs(LOW_RATE_LIMIT_INTERVAL_SECS))
+                    };
+
+                    if let Some(delay) = throttle {
+                        println!("Holding off requests to {} for {}s", source.name(), delay.as_secs());
+                        throttled_until = Instant::now() + delay;
+                        control.send(TriggerControl::Throttle(delay)).unwrap_or_else(|e| eprintln!("Send error: {}", e));
+                    }
+
+                    source_error = match result {
+                        Ok(data) => {
+                            // Sent with the bounds queried, so that aircraft which have only left the view are not lost
+                            out.send((data, query.bounds)).unwrap_or_else(|e| eprintln!("Send error: {}", e));
+                            None
+                        },
+                        Err(e) => {
+                            eprintln!("Failed to retrieve data from {} ({})", source.name(), e);
+                            Some(e.to_string())
+                        }
+                    };
+                }
+
+                status.send(SimulationStatus {
+                    paused: clock.is_paused(),
+                    speed: clock.speed(),
+                    timeline: timeline.as_ref().map(|x| (x.start(), x.end())),
+                    source_error: source_error.clone(),
+                    requests_remaining: source.rate_limit().and_then(|x| x.remaining)
+                }).unwrap_or_else(|e| eprintln!("Send error: {}", e));
+
+                Ok(())
+            })
+            .expect("Failed to receive new data for simulation");
     }
 }
-impl From<SendError<FlightData>> for RetrievalError {
-    fn from(error: SendError<FlightData>) -> RetrievalError {
-        RetrievalError::FlightChannelSendError(error)
+
+fn apply_playback_command(clock: &mut SimulationClock, timeline: Option<&Timeline>, control: &Sender<TriggerControl>, command: PlaybackCommand) {
+    let send = |x| control.send(x).unwrap_or_else(|e| eprintln!("Failed to adjust periodic trigger ({})", e));
+
+    match (command, timeline) {
+        (PlaybackCommand::TogglePause, _) if clock.is_paused() => {
+            // Resuming at the end of a recorded session replays it from the start, rather than pausing again
+            if let Some(x) = timeline.filter(|x| clock.now() >= x.end() as f64) {
+                clock.seek(x.start() as f64);
+            }
+            clock.resume();
+            send(TriggerControl::Resume);
+        },
+        (PlaybackCommand::TogglePause, _) => {
+            clock.pause();
+            send(TriggerControl::Pause);
+        },
+        (PlaybackCommand::SetSpeed(speed), Some(_)) => {
+            clock.set_speed(speed);
+            send(TriggerControl::SetRate(speed));
+        },
+        (PlaybackCommand::Step(offset), Some(x)) => {
+            if let Some(time) = x.step(clock.now() as isize, offset) {
+                clock.pause();
+                clock.seek(time as f64);
+                send(TriggerControl::Pause);
+            }
+        },
+        (PlaybackCommand::Seek(time), Some(x)) => {
+            clock.seek(time.clamp(x.start(), x.end()) as f64);
+        },
+        (_, None) => println!("Speed and position can only be changed when replaying a recorded session")
     }
 }
 
+pub fn periodic_trigger(trigger: Sender<SimulationTrigger>, control: Receiver<TriggerControl>, interval: u64) {
+    let base_interval = Duration::from_secs(interval);
+    let (mut interval, mut paused) = (base_interval, false);
+    let mut next = Instant::now() + interval;
+    let mut throttled_until = Instant::now();
 
+    loop {
+        // Wait for the next trigger, unless the cadence is changed in the meantime
+        let timeout = if paused { base_interval } else { next.saturating_duration_since(Instant::now()) };
+        match control.recv_timeout(timeout) {
+            Ok(TriggerControl::Pause) => paused = true,
+            Ok(TriggerControl::Resume) => {
+                paused = false;
+                next = (Instant::now() + interval).max(throttled_until);
+            },
+            Ok(TriggerControl::SetRate(rate)) => {
+                interval = base_interval.div_f64(rate.max(1.0))
+                    .max(Duration::from_millis(MIN_TRIGGER_INTERVAL_MS));
+                next = (Instant::now() + interval).max(throttled_until);
+            },
+            Ok(TriggerControl::Throttle(delay)) => {
+                throttled_until = Instant::now() + delay;
+                next = next.max(throttled_until);
+            },
+            Err(RecvTimeoutError::Timeout) if !paused => {
+                next = Instant::now() + interval;
+                trigger
+                    .send(SimulationTrigger::Refresh)
+                    .expect("Failed to trigger periodic simulation");
+            },
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => panic!("Periodic trigger control channel closed")
+        }
+    };
+}
 
+pub fn retrieve_flight_data(provider: sources::SourceProvider, request: Receiver<FlightDataRequest>, out: Sender<FlightDataResponse>) {
+    let (mut client, mut cache) = (provider.client(), caching::response_cache());
+    let mut board: Option<(String, SimulationClock)> = None;
+    let mut board_window: Option<(i64, i64)> = None;       // Window of the most recent board retrieval
+    let mut next_board_refresh = Instant::now();
 
+    loop {
+        // Wait for the next request, or until the airport board (if any) is due to be refreshed
+        let received = match board {
+            Some(_) => request.recv_timeout(next_board_refresh.saturating_duration_since(Instant::now())),
+            None => request.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        };
+
+        let response = match received {
+            Ok(FlightDataRequest::Flights(icao24, source)) => {
+                println!("Retrieving flight data for \"{}\"...", icao24);
+                perform_flight_data_lookup::<FlightData>(&mut cache, &mut client, source).map(FlightDataResponse::Flights)
+            },
+            Ok(FlightDataRequest::Track(icao24, source)) => {
+                println!("Retrieving track for \"{}\"...", icao24);
+                perform_flight_data_lookup::<Track>(&mut cache, &mut client, source).map(FlightDataResponse::Track)
+            },
+            // Refresh immediately for a new airport, or if the simulation clock has moved on to a different window
+            Ok(FlightDataRequest::AirportBoard(airport, clock)) => {
+                let changed = airport.as_deref() != board.as_ref().map(|x| x.0.as_str());
+                if changed || board_window != Some(airport_board_window(clock.now() as i64)) {
+                    next_board_refresh = Instant::now();
+                }
+
+                board = airport.map(|x| (x, clock));
+                None
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                next_board_refresh = Instant::now() + AIRPORT_BOARD_REFRESH_INTERVAL;
+                board.as_ref().map(|(airport, clock)| {
+                    let time = clock.now() as i64;
+                    board_window = Some(airport_board_window(time));
 
+                    FlightDataResponse::AirportBoard(airport.clone(), retrieve_airport_board(&provider, &mut cache, &mut client, airport, time))
+                })
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                println!("Flight data request channel closed");
+                return;
+            }
+        };
+
+        if let Some(response) = response {
+            out.send(response)
+                .map(|_| println!("Retrieved flight data successfully"))
+                .unwrap_or_else(|e| println!("Failed to return flight data to simulation ({})", e));
+        }
+    }
+}
 
+fn retrieve_airport_board(provider: &sources::SourceProvider, cache: &mut ResponseCache, client: &mut HttpClient,
+                          airport: &str, time: i64) -> Option<AirportBoard> {
+    println!("Retrieving airport board for \"{}\"...", airport);
+    let (start, end) = airport_board_window(time);
 
+    Some(AirportBoard {
+        airport: airport.to_string(),
+        arrivals: perform_airport_flights_lookup(cache, client, provider.source_airport_arrivals(airport, start, end))?,
+        departures: perform_airport_flights_lookup(cache, client, provider.source_airport_departures(airport, start, end))?,
+        time
+    })
+}
 
+// Window of flights listed on the board at the given time.  It only moves on at each refresh interval, so that
+// repeated requests for it can be served from the cache
+fn airport_board_window(time: i64) -> (i64, i64) {
+    let interval = AIRPORT_BOARD_REFRESH_INTERVAL.as_secs() as i64;
+    let centre = time.div_euclid(interval) * interval;
 
+    (centre - AIRPORT_BOARD_WINDOW_SECS / 2, centre + AIRPORT_BOARD_WINDOW_SECS / 2)
+}
 
+// OpenSky reports "404 Not Found", rather than an empty array, when no flights match an airport query
+fn perform_airport_flights_lookup(cache: &mut ResponseCache, client: &mut HttpClient, source: sources::Source) -> Option<FlightData> {
+    match caching::retrieve(cache, client, &source) {
+        Err(SourceError::Http(HttpError::Status(404))) => Some(FlightData::new()),
+        result => result
+            .and_then(|x| Ok(serde_json::from_str::<FlightData>(x.as_str())?))
+            .map_err(|e| eprintln!("Failed to retrieve \"{}\" ({})", source.get_path(), e))
+            .ok()
+    }
+}
 
+fn perform_flight_data_lookup<T>(cache: &mut ResponseCache, client: &mut HttpClient, source: sources::Source) -> Option<T>
+    where T: DeserializeOwned {
+    caching::retrieve(cache, client, &source)
+        .and_then(|x| Ok(serde_json::from_str::<T>(x.as_str())?))
+        .map_err(|e| eprintln!("Failed to retrieve \"{}\" ({})", source.get_path(), e))
+        .ok()
+}
 
+#[cfg(test)]
+mod tests {
+    use super::{apply_playback_command, PlaybackCommand};
+    use crate::simulation::clock::SimulationClock;
+    use crate::sources::state_vectors::Timeline;
+    use std::sync::mpsc;
 
+    #[test]
+    fn test_resume_at_end_of_timeline() {
+        let timeline = Timeline::new(vec![1000, 1010, 1020]);
+        let (control, _rx) = mpsc::channel();
 
+        let mut clock = SimulationClock::new(1020.0, 1.0);
+        clock.pause();
+        apply_playback_command(&mut clock, Some(&timeline), &control, PlaybackCommand::TogglePause);
+        assert!(!clock.is_paused());
+        assert!(clock.now() >= 1000.0 && clock.now() < 1010.0);
 
+        // Elsewhere in the session, playback resumes from the current position
+        clock.pause();
+        clock.seek(1010.0);
+        apply_playback_command(&mut clock, Some(&timeline), &control, PlaybackCommand::TogglePause);
+        assert!(!clock.is_paused() && clock.now() >= 1010.0);
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::mpsc::{self, Receiver};
+use crate::adsb::{AdsbTracker, ModeSFrame};
+use crate::data::aircraft::AircraftData;
+use crate::sources::receiver::{self, ReceiverState, LineBuffer};
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+use crate::util::temporal::get_current_timestamp_secs;
+
+pub const DEFAULT_PORT: u16 = 30002;
+
+// State vectors decoded from a feed of raw Mode-S frames in AVR hex format, e.g. "*8D4840D6...;"
+pub struct AvrSource {
+    address: String,
+    feed: Receiver<Vec<u8>>,
+    lines: LineBuffer,
+    tracker: AdsbTracker,
+    state: ReceiverState
+}
+
+impl AvrSource {
+    // Address is of the form "host:port".  Receiver location (lat, lon) allows positions to be
+    // resolved from single frames, rather than waiting for an even/odd pair
+    pub fn connect(address: &str, location: Option<(f64, f64)>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        receiver::spawn_tcp_feed(address.to_string(), tx);
+
+        Self {
+            address: address.to_string(),
+            feed: rx,
+            lines: LineBuffer::new(),
+            tracker: AdsbTracker::new(location),
+            state: ReceiverState::new(receiver::DEFAULT_STALE_AFTER_SECS)
+        }
+    }
+
+    fn process_pending(&mut self, time: i64) {
+        let (feed, lines, tracker, state) = (&self.feed, &mut self.lines, &mut self.tracker, &mut self.state);
+
+        feed.try_iter()
+            .flat_map(|data| lines.push(&data))
+            .filter_map(|line| ModeSFrame::parse_avr(&line))
+            .for_each(|frame| tracker.apply(state, &frame, time));
+    }
+}
+
+impl StateVectorSource for AvrSource {
+    fn name(&self) -> String {
+        format!("AVR feed ({})", self.address)
+    }
+
+    fn fetch(&mut self, _query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        let time = get_current_timestamp_secs();
+        self.process_pending(time);
+
+        Ok(self.state.snapshot(time))
+    }
+}
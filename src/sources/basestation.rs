@@ -0,0 +1,217 @@
+use std::sync::mpsc::{self, Receiver};
+use crate::data::aircraft::{Aircraft, AircraftData};
+use crate::sources::receiver::{self, ReceiverState, LineBuffer};
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+use crate::util::temporal::get_current_timestamp_secs;
+use crate::util::units::{Altitude, Speed, VerticalRate};
+
+pub const DEFAULT_PORT: u16 = 30003;
+
+const FIELD_COUNT: usize = 22;
+
+// State vectors assembled from a BaseStation (SBS-1) CSV feed, as exposed by dump1090 and similar
+pub struct BaseStationSource {
+    address: String,
+    feed: Receiver<Vec<u8>>,
+    lines: LineBuffer,
+    state: ReceiverState
+}
+
+// Single MSG record from the feed.  Every field other than the address is only present in
+// some transmission types, e.g. callsign in MSG,1 and position in MSG,2 and MSG,3
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbsMessage {
+    pub transmission_type: u8,              // 1..8
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub altitude: Option<f32>,              // Feet
+    pub ground_speed: Option<f32>,          // Knots
+    pub track: Option<f32>,                 // Decimal degrees clockwise from N
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub vertical_rate: Option<f32>,         // Feet per minute
+    pub squawk: Option<String>,
+    pub emergency: Option<bool>,
+    pub spi: Option<bool>,
+    pub on_ground: Option<bool>
+}
+
+impl BaseStationSource {
+    // Address is of the form "host:port"
+    pub fn connect(address: &str) -> Self {
+        let (tx, rx) = mpsc::channel();
+        receiver::spawn_tcp_feed(address.to_string(), tx);
+
+        Self {
+            address: address.to_string(),
+            feed: rx,
+            lines: LineBuffer::new(),
+            state: ReceiverState::new(receiver::DEFAULT_STALE_AFTER_SECS)
+        }
+    }
+
+    fn process_pending(&mut self, time: i64) {
+        let (feed, lines, state) = (&self.feed, &mut self.lines, &mut self.state);
+
+        feed.try_iter()
+            .flat_map(|data| lines.push(&data))
+            .filter_map(|line| SbsMessage::parse(&line))
+            .for_each(|msg| state.update(&msg.icao24, time, |aircraft| msg.apply(aircraft, time)));
+    }
+}
+
+impl StateVectorSource for BaseStationSource {
+    fn name(&self) -> String {
+        format!("BaseStation feed ({})", self.address)
+    }
+
+    fn fetch(&mut self, _query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        let time = get_current_timestamp_secs();
+        self.process_pending(time);
+
+        Ok(self.state.snapshot(time))
+    }
+}
+
+impl SbsMessage {
+    // Returns None for anything other than a well-formed MSG record
+    pub fn parse(line: &str) -> Option<Self> {
+        let fields = line.trim().split(',').map(|x| x.trim()).collect::<Vec<&str>>();
+        if fields.len() < FIELD_COUNT || fields[0] != "MSG" { return None }
+
+        let transmission_type = fields[1].parse::<u8>().ok().filter(|t| (1..=8).contains(t))?;
+        let icao24 = Some(fields[4]).filter(|x| x.len() == 6)?.to_lowercase();
+
+        let value = |ix: usize| Some(fields[ix]).filter(|x| !x.is_empty());
+        let flag = |ix: usize| value(ix).map(|x| x != "0");
+
+        Some(Self {
+            transmission_type,
+            icao24,
+            callsign: value(10).map(|x| x.to_string()),
+            altitude: value(11).and_then(|x| x.parse().ok()),
+            ground_speed: value(12).and_then(|x| x.parse().ok()),
+            track: value(13).and_then(|x| x.parse().ok()),
+            latitude: value(14).and_then(|x| x.parse().ok()),
+            longitude: value(15).and_then(|x| x.parse().ok()),
+            vertical_rate: value(16).and_then(|x| x.parse().ok()),
+            squawk: value(17).map(|x| x.to_string()),
+            emergency: flag(19),
+            spi: flag(20),
+            on_ground: flag(21)
+        })
+    }
+
+    // Merges all fields present in this message into the given state vector
+    pub fn apply(&self, aircraft: &mut Aircraft, time: i64) {
+        if let Some(callsign) = &self.callsign { aircraft.callsign = Some(callsign.clone()); }
+        if let Some(alt) = self.altitude { aircraft.baro_altitude = Some(Altitude::from_feet(alt)); }
+        if let Some(gs) = self.ground_speed { aircraft.velocity = Some(Speed::from_knots(gs)); }
+        if let Some(track) = self.track { aircraft.true_track = Some(track); }
+        if let Some(vr) = self.vertical_rate { aircraft.vertical_rate = Some(VerticalRate::from_feet_per_min(vr)); }
+        if let Some(squawk) = self.squawk.as_ref().and_then(|x| x.parse().ok()) { aircraft.squawk = Some(squawk); }
+        if let Some(spi) = self.spi { aircraft.spi = spi; }
+        if let Some(on_ground) = self.on_ground { aircraft.on_ground = on_ground; }
+
+        if let (Some(lon), Some(lat)) = (self.longitude, self.latitude) {
+            aircraft.longitude = Some(lon);
+            aircraft.latitude = Some(lat);
+            aircraft.time_position = Some(time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+    use super::{BaseStationSource, SbsMessage};
+    use crate::data::aircraft::Squawk;
+    use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery};
+
+    const CAPTURE: &str = "\
+MSG,1,1,1,4CA2D6,1,2019/08/18,14:20:01.123,2019/08/18,14:20:01.150,RYR4TL  ,,,,,,,,,,,0
+MSG,3,1,1,4CA2D6,1,2019/08/18,14:20:01.456,2019/08/18,14:20:01.480,,36000,,,53.35921,-6.27133,,,0,0,0,0
+MSG,4,1,1,4CA2D6,1,2019/08/18,14:20:01.789,2019/08/18,14:20:01.800,,,450.5,270.0,,,-1024,,,,,0
+MSG,6,1,1,4CA2D6,1,2019/08/18,14:20:02.000,2019/08/18,14:20:02.010,,36000,,,,,,7700,-1,-1,0,0
+STA,,1,1,4CA2D6,1,2019/08/18,14:20:02.100,2019/08/18,14:20:02.110,RM
+MSG,8,1,1,406B90,1,2019/08/18,14:20:02.200,2019/08/18,14:20:02.210,,,,,,,,,,,,-1
+";
+
+    #[test]
+    fn test_parse_messages() {
+        let msgs = CAPTURE.lines().map(SbsMessage::parse).collect::<Vec<Option<SbsMessage>>>();
+
+        let ident = msgs[0].as_ref().unwrap();
+        assert_eq!(ident.transmission_type, 1);
+        assert_eq!(ident.icao24, "4ca2d6");
+        assert_eq!(ident.callsign, Some("RYR4TL".to_string()));
+        assert_eq!(ident.on_ground, Some(false));
+
+        let position = msgs[1].as_ref().unwrap();
+        assert_eq!(position.altitude, Some(36000.0));
+        assert_eq!(position.latitude, Some(53.35921));
+        assert_eq!(position.longitude, Some(-6.27133));
+        assert_eq!(position.callsign, None);
+
+        let velocity = msgs[2].as_ref().unwrap();
+        assert_eq!(velocity.ground_speed, Some(450.5));
+        assert_eq!(velocity.track, Some(270.0));
+        assert_eq!(velocity.vertical_rate, Some(-1024.0));
+
+        let surveillance = msgs[3].as_ref().unwrap();
+        assert_eq!(surveillance.squawk, Some("7700".to_string()));
+        assert_eq!(surveillance.emergency, Some(true));
+
+        assert!(msgs[4].is_none());
+        assert_eq!(msgs[5].as_ref().unwrap().on_ground, Some(true));
+    }
+
+    #[test]
+    fn test_reject_malformed_messages() {
+        assert!(SbsMessage::parse("").is_none());
+        assert!(SbsMessage::parse("MSG,9,1,1,4CA2D6,1,,,,,,,,,,,,,,,,0").is_none());
+        assert!(SbsMessage::parse("MSG,3,1,1,4CA2D6,1").is_none());
+        assert!(SbsMessage::parse("MSG,3,1,1,,1,,,,,,,,,,,,,,,,0").is_none());
+    }
+
+    #[test]
+    fn test_merge_from_tcp_feed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // Stand-in receiver which replays the capture, split mid-line across writes
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let (first, second) = CAPTURE.split_at(100);
+            stream.write_all(first.as_bytes()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            stream.write_all(second.as_bytes()).unwrap();
+            thread::sleep(Duration::from_secs(5));
+        });
+
+        let mut source = BaseStationSource::connect(&address);
+        let mut data = vec![];
+        for _ in 0..100 {
+            data = source.fetch(&StateVectorQuery::default()).unwrap().data;
+            if data.len() == 2 { break }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        assert_eq!(data.len(), 2);
+        let aircraft = data.iter().find(|x| x.icao24 == "4ca2d6").unwrap();
+        assert_eq!(aircraft.callsign, Some("RYR4TL".to_string()));
+        assert_eq!(aircraft.latitude, Some(53.35921));
+        assert_eq!(aircraft.longitude, Some(-6.27133));
+        assert!((aircraft.baro_altitude.unwrap().metres() - 10972.8).abs() < 0.01);
+        assert!((aircraft.velocity.unwrap().metres_per_sec() - 231.76).abs() < 0.01);
+        assert!((aircraft.vertical_rate.unwrap().metres_per_sec() - -5.2019).abs() < 0.001);
+        assert_eq!(aircraft.squawk, Some(Squawk::EMERGENCY));
+        assert!(aircraft.time_position.is_some());
+        assert!(!aircraft.on_ground);
+
+        assert!(data.iter().find(|x| x.icao24 == "406b90").unwrap().on_ground);
+    }
+}
@@ -0,0 +1,272 @@
+use std::sync::mpsc::{self, Receiver};
+use crate::adsb::{AdsbTracker, ModeSFrame};
+use crate::data::aircraft::AircraftData;
+use crate::sources::receiver::{self, ReceiverState};
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+use crate::util::temporal::get_current_timestamp_secs;
+
+pub const DEFAULT_PORT: u16 = 30005;
+
+const ESCAPE: u8 = 0x1a;
+const TIMESTAMP_LENGTH: usize = 6;
+const SIGNAL_LENGTH: usize = 1;
+
+const FRAME_MODE_AC: u8 = b'1';
+const FRAME_MODE_S_SHORT: u8 = b'2';
+const FRAME_MODE_S_LONG: u8 = b'3';
+
+// State vectors assembled from a Mode-S Beast binary feed, as exposed by readsb and dump1090-fa
+pub struct BeastSource {
+    address: String,
+    feed: Receiver<Vec<u8>>,
+    decoder: BeastDecoder,
+    tracker: AdsbTracker,
+    state: ReceiverState
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BeastFrameType {
+    ModeAC,
+    ModeSShort,
+    ModeSLong
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeastFrame {
+    pub frame_type: BeastFrameType,
+    pub timestamp: u64,                     // 48-bit MLAT timestamp, 12MHz counter
+    pub signal: u8,                         // Raw signal level
+    pub data: Vec<u8>
+}
+
+// Incremental decoder for the Beast framing: <0x1a> <type> <timestamp:6> <signal:1> <data:n>, where
+// any 0x1a byte after the type is escaped by doubling it.  Data may be pushed in arbitrary chunks
+#[derive(Default)]
+pub struct BeastDecoder {
+    buffer: Vec<u8>
+}
+
+impl BeastSource {
+    // Address is of the form "host:port".  Receiver location (lat, lon) allows positions to be
+    // resolved from single frames, rather than waiting for an even/odd pair
+    pub fn connect(address: &str, location: Option<(f64, f64)>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        receiver::spawn_tcp_feed(address.to_string(), tx);
+
+        Self {
+            address: address.to_string(),
+            feed: rx,
+            decoder: BeastDecoder::new(),
+            tracker: AdsbTracker::new(location),
+            state: ReceiverState::new(receiver::DEFAULT_STALE_AFTER_SECS)
+        }
+    }
+
+    fn process_pending(&mut self, time: i64) {
+        let (feed, decoder, tracker, state) = (&self.feed, &mut self.decoder, &mut self.tracker, &mut self.state);
+
+        feed.try_iter()
+            .flat_map(|data| decoder.push(&data))
+            .filter_map(|frame| frame.to_mode_s())
+            .for_each(|frame| tracker.apply(state, &frame, time));
+    }
+}
+
+impl StateVectorSource for BeastSource {
+    fn name(&self) -> String {
+        format!("Beast feed ({})", self.address)
+    }
+
+    fn fetch(&mut self, _query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        let time = get_current_timestamp_secs();
+        self.process_pending(time);
+
+        Ok(self.state.snapshot(time))
+    }
+}
+
+impl BeastFrameType {
+    fn from_type_byte(b: u8) -> Option<Self> {
+        match b {
+            FRAME_MODE_AC => Some(BeastFrameType::ModeAC),
+            FRAME_MODE_S_SHORT => Some(BeastFrameType::ModeSShort),
+            FRAME_MODE_S_LONG => Some(BeastFrameType::ModeSLong),
+            _ => None
+        }
+    }
+
+    fn data_length(self) -> usize {
+        match self {
+            BeastFrameType::ModeAC => 2,
+            BeastFrameType::ModeSShort => 7,
+            BeastFrameType::ModeSLong => 14
+        }
+    }
+}
+
+impl BeastFrame {
+    // Signal level in dBFS, derived from the receiver's magnitude byte
+    pub fn rssi(&self) -> f32 {
+        let level = (self.signal as f32) / 255.0;
+        10.0 * (level * level).max(1e-10).log10()
+    }
+
+    // Mode A/C replies carry no address and are not converted
+    pub fn to_mode_s(&self) -> Option<ModeSFrame> {
+        match self.frame_type {
+            BeastFrameType::ModeAC => None,
+            _ => Some(ModeSFrame { data: self.data.clone(), timestamp: Some(self.timestamp), rssi: Some(self.rssi()) })
+        }
+    }
+}
+
+impl BeastDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns all frames completed by this data.  Any unrecognised or truncated frames are skipped
+    pub fn push(&mut self, data: &[u8]) -> Vec<BeastFrame> {
+        self.buffer.extend_from_slice(data);
+
+        let mut frames = vec![];
+        loop {
+            // Discard anything preceding the next sync byte
+            match self.buffer.iter().position(|&b| b == ESCAPE) {
+                Some(ix) => { self.buffer.drain(..ix); },
+                None => { self.buffer.clear(); break }
+            }
+            if self.buffer.len() < 2 { break }
+
+            let frame_type = match BeastFrameType::from_type_byte(self.buffer[1]) {
+                Some(t) => t,
+                None => { self.buffer.drain(..1); continue }      // Not a frame we understand; resync
+            };
+
+            match self.read_frame(frame_type) {
+                FrameRead::Complete(frame, consumed) => { frames.push(frame); self.buffer.drain(..consumed); },
+                FrameRead::Truncated(consumed) => { self.buffer.drain(..consumed); },
+                FrameRead::Incomplete => break
+            }
+        }
+
+        frames
+    }
+
+    // Attempts to read a frame of the given type from the start of the buffer
+    fn read_frame(&self, frame_type: BeastFrameType) -> FrameRead {
+        let length = TIMESTAMP_LENGTH + SIGNAL_LENGTH + frame_type.data_length();
+        let mut content = Vec::with_capacity(length);
+
+        let mut ix = 2;
+        while content.len() < length {
+            match (self.buffer.get(ix), self.buffer.get(ix + 1)) {
+                (None, _) | (Some(&ESCAPE), None) => return FrameRead::Incomplete,
+                (Some(&ESCAPE), Some(&ESCAPE)) => { content.push(ESCAPE); ix += 2; },
+                (Some(&ESCAPE), Some(_)) => return FrameRead::Truncated(ix),  // Start of the next frame
+                (Some(&b), _) => { content.push(b); ix += 1; }
+            }
+        }
+
+        let timestamp = content[..TIMESTAMP_LENGTH].iter()
+            .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+
+        FrameRead::Complete(BeastFrame {
+            frame_type,
+            timestamp,
+            signal: content[TIMESTAMP_LENGTH],
+            data: content[(TIMESTAMP_LENGTH + SIGNAL_LENGTH)..].to_vec()
+        }, ix)
+    }
+}
+
+enum FrameRead {
+    Complete(BeastFrame, usize),            // Frame and number of raw bytes consumed
+    Truncated(usize),                       // Frame interrupted by another; skip the consumed bytes
+    Incomplete                              // More data required
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BeastDecoder, BeastFrameType};
+    use crate::adsb::AdsbTracker;
+    use crate::sources::receiver::ReceiverState;
+
+    // Recorded stream: a DF17 identification frame, a Mode A/C frame, and a DF11 all-call reply
+    // whose timestamp and signal level both contain escaped 0x1a bytes
+    const STREAM: [u8; 64] = [
+        0x1a, 0x33, 0x00, 0x00, 0x2f, 0x4c, 0x82, 0x19, 0x5b,
+        0x8d, 0x48, 0x40, 0xd6, 0x20, 0x2c, 0xc3, 0x71, 0xc3, 0x2c, 0xe0, 0x57, 0x60, 0x98,
+        0x1a, 0x31, 0x00, 0x00, 0x2f, 0x4c, 0x90, 0x01, 0x20, 0x05, 0x10,
+        0x1a, 0x32, 0x00, 0x01, 0x1a, 0x1a, 0x3c, 0x10, 0x02, 0x1a, 0x1a,
+        0x5d, 0x48, 0x4f, 0xde, 0xa2, 0x48, 0xf5,
+        0x00, 0x1a, 0x32, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x5d, 0x48
+    ];
+
+    #[test]
+    fn test_decode_recorded_stream() {
+        let frames = BeastDecoder::new().push(&STREAM);
+        assert_eq!(frames.len(), 3);
+
+        assert_eq!(frames[0].frame_type, BeastFrameType::ModeSLong);
+        assert_eq!(frames[0].timestamp, 0x2f4c8219);
+        assert_eq!(frames[0].signal, 0x5b);
+        assert_eq!(frames[0].data, vec![0x8d, 0x48, 0x40, 0xd6, 0x20, 0x2c, 0xc3, 0x71, 0xc3, 0x2c, 0xe0, 0x57, 0x60, 0x98]);
+
+        assert_eq!(frames[1].frame_type, BeastFrameType::ModeAC);
+        assert_eq!(frames[1].data, vec![0x05, 0x10]);
+
+        assert_eq!(frames[2].frame_type, BeastFrameType::ModeSShort);
+        assert_eq!(frames[2].timestamp, 0x00011a3c1002);
+        assert_eq!(frames[2].signal, 0x1a);
+        assert_eq!(frames[2].data, vec![0x5d, 0x48, 0x4f, 0xde, 0xa2, 0x48, 0xf5]);
+    }
+
+    #[test]
+    fn test_decode_in_arbitrary_chunks() {
+        let mut decoder = BeastDecoder::new();
+        let frames = STREAM.chunks(5)
+            .flat_map(|chunk| decoder.push(chunk))
+            .collect::<Vec<_>>();
+
+        assert_eq!(frames, BeastDecoder::new().push(&STREAM));
+
+        // Final partial frame is completed once the remainder arrives
+        let remainder = decoder.push(&[0x4f, 0xde, 0xa2, 0x48, 0xf5]);
+        assert_eq!(remainder.len(), 1);
+        assert_eq!(remainder[0].signal, 0xff);
+    }
+
+    #[test]
+    fn test_resync_after_truncated_frame() {
+        let mut stream = vec![0xff, 0x1a, 0x33, 0x00, 0x01, 0x02];       // Garbage, then a frame cut short
+        stream.extend_from_slice(&STREAM[..23]);
+
+        let frames = BeastDecoder::new().push(&stream);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].timestamp, 0x2f4c8219);
+    }
+
+    #[test]
+    fn test_signal_level() {
+        let frames = BeastDecoder::new().push(&STREAM);
+        assert!((frames[0].rssi() - -8.94).abs() < 0.01);
+        assert_eq!(frames[1].to_mode_s(), None);
+        assert_eq!(frames[2].to_mode_s().unwrap().timestamp, Some(0x00011a3c1002));
+    }
+
+    #[test]
+    fn test_frames_update_aircraft_state() {
+        let (mut tracker, mut state) = (AdsbTracker::new(None), ReceiverState::new(60));
+        BeastDecoder::new().push(&STREAM).iter()
+            .filter_map(|frame| frame.to_mode_s())
+            .for_each(|frame| tracker.apply(&mut state, &frame, 1000));
+
+        let mut data = state.snapshot(1000).data;
+        data.sort_by(|a, b| a.icao24.cmp(&b.icao24));
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].icao24, "4840d6");
+        assert_eq!(data[0].callsign, Some("KLM1023".to_string()));
+        assert_eq!(data[1].icao24, "484fde");
+    }
+}
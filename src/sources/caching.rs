@@ -1,6 +1,46 @@
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::time::{Duration, SystemTime};
+use serde::*;
+use crate::sources::httpclient::HttpClient;
+use crate::sources::sources::Source;
+use crate::sources::state_vectors::SourceError;
+use crate::util::temporal::get_current_timestamp_secs;
 
 const DEFAULT_LOCATION: &str = "cache";
+const METADATA_EXTENSION: &str = ".meta";
+
+const DEFAULT_MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+const DEFAULT_MAX_DISK_BYTES: u64 = 256 * 1024 * 1024;
+
+// Cache of raw responses, as used by all remote sources
+pub type ResponseCache = FilesystemCache<String, fn(&String) -> String>;
+
+// Details of the original retrieval, stored in a sidecar file alongside each cached response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheMetadata {
+    pub url: String,
+    pub fetched_at: i64,
+    pub status: u16,
+
+    #[serde(default)]
+    pub pinned: bool                        // Served regardless of age and never evicted, e.g. a snapshot kept for offline use
+}
+
+// Upper bounds on the size of cached content, based on the raw length of each response
+#[derive(Debug, Clone)]
+pub struct CacheLimits {
+    pub max_memory_bytes: usize,
+    pub max_disk_bytes: u64
+}
+
+struct CacheEntry<V> {
+    value: V,
+    metadata: CacheMetadata,
+    size: usize,
+    last_used: u64
+}
 
 pub struct FilesystemCache<V, F>
     where V: Clone,
@@ -8,7 +48,16 @@ pub struct FilesystemCache<V, F>
 {
     location: String,
     transform: F,
-    data: HashMap<String, V>
+    limits: CacheLimits,
+    data: HashMap<String, CacheEntry<V>>,
+    memory_used: usize,
+    access_count: u64                       // Logical clock for LRU ordering of in-memory entries
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self { max_memory_bytes: DEFAULT_MAX_MEMORY_BYTES, max_disk_bytes: DEFAULT_MAX_DISK_BYTES }
+    }
 }
 
 impl<V, F> FilesystemCache<V, F>
@@ -16,35 +65,270 @@ impl<V, F> FilesystemCache<V, F>
           F: Fn(&String) -> V
 {
     pub fn new_from_location(location: &str, transform: F) -> Self {
-        Self { location: String::from(location), transform, data: HashMap::new() }
+        Self { location: String::from(location), transform, limits: CacheLimits::default(),
+               data: HashMap::new(), memory_used: 0, access_count: 0 }
     }
 
     pub fn new(transform: F) -> Self {
         Self::new_from_location(DEFAULT_LOCATION, transform)
     }
 
-    pub fn get(&mut self, key: &String) -> std::io::Result<V> {
-        self.data.get(key)
-            .map(|x| Ok(x.clone()))                                 // Either return locally-cached version
-            .unwrap_or_else(|| {                                         // Or attempt to retrieve from filesystem
-                std::fs::read_to_string(&self.cache_path(key))
-                    .map(|x| (self.transform)(&x))
-                    .and_then(|x| {
-                        self.data.insert(key.clone(), x.clone());     // If we could read from disk, store locally
-                        Ok(x)
-                    })
-                    .or_else(|e| Result::Err(e))
-            })
+    #[cfg(test)]
+    pub fn with_limits(self, limits: CacheLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    // Returns the cached value for this key, unless it is missing or older than the given TTL and not pinned.
+    // Entries without metadata cannot be aged and are treated as missing
+    pub fn get(&mut self, key: &str, ttl: Duration) -> io::Result<Option<V>> {
+        let now = get_current_timestamp_secs();
+        let is_fresh = |metadata: &CacheMetadata| metadata.pinned || now - metadata.fetched_at <= ttl.as_secs() as i64;
+
+        self.access_count += 1;
+        if let Some(entry) = self.data.get_mut(key) {
+            if is_fresh(&entry.metadata) {
+                entry.last_used = self.access_count;
+                let value = entry.value.clone();
+
+                touch(&self.cache_path(key));
+                return Ok(Some(value));
+            }
+
+            // The copy on disk is the same age, so there is no point checking it
+            self.remove_from_memory(key);
+            return Ok(None);
+        }
+
+        let metadata = match self.read_metadata(key)? {
+            Some(metadata) if is_fresh(&metadata) => metadata,
+            _ => return Ok(None)
+        };
+
+        let raw = match fs::read_to_string(self.cache_path(key)) {
+            Ok(x) => x,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e)
+        };
+
+        touch(&self.cache_path(key));
+        let value = (self.transform)(&raw);
+        self.insert(key, value.clone(), metadata, raw.len());
+
+        Ok(Some(value))
+    }
+
+    // Stores a response on disk and in memory, evicting the least recently used entries if over capacity
+    pub fn put(&mut self, key: &str, raw: &str, metadata: CacheMetadata) -> io::Result<()> {
+        fs::create_dir_all(&self.location)?;
+        fs::write(self.cache_path(key), raw)?;
+        fs::write(self.metadata_path(key), serde_json::to_string(&metadata)?)?;
+
+        let value = (self.transform)(&raw.to_string());
+        self.insert(key, value, metadata, raw.len());
+
+        self.evict_from_disk()
+    }
+
+    fn insert(&mut self, key: &str, value: V, metadata: CacheMetadata, size: usize) {
+        self.remove_from_memory(key);
+
+        self.access_count += 1;
+        self.data.insert(key.to_string(), CacheEntry { value, metadata, size, last_used: self.access_count });
+        self.memory_used += size;
+
+        while self.memory_used > self.limits.max_memory_bytes {
+            let lru = self.data.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+
+            match lru {
+                Some(key) => self.remove_from_memory(&key),
+                None => break
+            }
+        }
+    }
+
+    fn remove_from_memory(&mut self, key: &str) {
+        if let Some(entry) = self.data.remove(key) {
+            self.memory_used -= entry.size;
+        }
+    }
+
+    // Removes the least recently used responses from disk, by modification time, until within capacity.  Pinned
+    // responses are kept, but still count towards the capacity
+    fn evict_from_disk(&mut self) -> io::Result<()> {
+        let mut entries = fs::read_dir(&self.location)?
+            .filter_map(|x| x.ok())
+            .filter(|x| !x.file_name().to_string_lossy().ends_with(METADATA_EXTENSION))
+            .filter_map(|x| x.metadata().ok().map(|m| (x.file_name().to_string_lossy().to_string(), m)))
+            .filter(|(_, m)| m.is_file())
+            .map(|(key, m)| (key, m.len(), m.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+            .collect::<Vec<(String, u64, SystemTime)>>();
+
+        let mut total = entries.iter().map(|(_, size, _)| size).sum::<u64>();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        for (key, size, _) in entries {
+            if total <= self.limits.max_disk_bytes { break; }
+            if self.read_metadata(&key)?.map(|x| x.pinned).unwrap_or(false) { continue; }
+
+            fs::remove_file(self.cache_path(&key))?;
+            fs::remove_file(self.metadata_path(&key)).unwrap_or(());
+            total -= size;
+        }
+
+        Ok(())
+    }
+
+    fn read_metadata(&self, key: &str) -> io::Result<Option<CacheMetadata>> {
+        match fs::read_to_string(self.metadata_path(key)) {
+            Ok(x) => Ok(serde_json::from_str(&x)
+                .map_err(|e| eprintln!("Ignoring invalid cache metadata for \"{}\" ({})", key, e))
+                .ok()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
     }
 
-    fn cache_path(&self, key: &String) -> String {
+    fn cache_path(&self, key: &str) -> String {
         format!("{}/{}", self.location, key)
     }
 
+    fn metadata_path(&self, key: &str) -> String {
+        format!("{}{}", self.cache_path(key), METADATA_EXTENSION)
+    }
+
     // Returns a normalised name suitable for use as key in the filesystem cache
     pub fn cache_key(&self, input_str: &str) -> String {
         input_str.chars()
             .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
             .collect::<String>()
     }
-}
\ No newline at end of file
+}
+
+pub fn response_cache() -> ResponseCache {
+    FilesystemCache::new(String::clone)
+}
+
+// Retrieves the given source via the cache, when enabled for it, falling back to the network on a
+// miss and writing the response back to the cache
+pub fn retrieve(cache: &mut ResponseCache, client: &mut HttpClient, source: &Source) -> Result<String, SourceError> {
+    if !source.should_use_cache() {
+        return Ok(client.get(source.get_path().as_str())?);
+    }
+
+    let key = cache.cache_key(&source.get_path());
+    match cache.get(&key, source.cache_ttl()) {
+        Ok(Some(x)) => return Ok(x),
+        Ok(None) => (),
+        Err(e) => eprintln!("Failed to read cache entry \"{}\" ({})", key, e)
+    }
+
+    let response = client.fetch(source.get_path().as_str())?;
+    let metadata = CacheMetadata {
+        url: source.get_path(),
+        fetched_at: get_current_timestamp_secs(),
+        status: response.status,
+        pinned: false
+    };
+
+    cache.put(&key, &response.body, metadata)
+        .unwrap_or_else(|e| eprintln!("Failed to write cache entry \"{}\" ({})", key, e));
+
+    Ok(response.body)
+}
+
+// Marks a cached response as recently used, for the purposes of disk eviction
+fn touch(path: &str) {
+    fs::OpenOptions::new().write(true).open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+        .unwrap_or_else(|e| eprintln!("Failed to update cache entry \"{}\" ({})", path, e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FilesystemCache, CacheLimits, CacheMetadata, ResponseCache};
+    use crate::data::aircraft::AircraftData;
+    use crate::util::temporal::get_current_timestamp_secs;
+    use std::time::Duration;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn test_location(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("flight-radar-cache-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&path).unwrap_or(());
+        path.to_str().unwrap().to_string()
+    }
+
+    fn metadata(url: &str, age: i64) -> CacheMetadata {
+        CacheMetadata { url: url.to_string(), fetched_at: get_current_timestamp_secs() - age, status: 200, pinned: false }
+    }
+
+    fn cache(location: &str) -> ResponseCache {
+        FilesystemCache::new_from_location(location, String::clone)
+    }
+
+    #[test]
+    fn test_write_through_and_expiry() {
+        let location = test_location("expiry");
+        let mut cache = cache(&location);
+
+        cache.put("fresh", "{\"time\":1}", metadata("https://a/fresh", 0)).unwrap();
+        cache.put("stale", "{\"time\":2}", metadata("https://a/stale", 120)).unwrap();
+        assert_eq!(cache.get("fresh", TTL).unwrap(), Some("{\"time\":1}".to_string()));
+        assert_eq!(cache.get("stale", TTL).unwrap(), None);
+        assert_eq!(cache.get("missing", TTL).unwrap(), None);
+
+        // Entries and their metadata persist for a new cache instance
+        let mut reloaded = self::cache(&location);
+        assert_eq!(reloaded.get("fresh", TTL).unwrap(), Some("{\"time\":1}".to_string()));
+        assert_eq!(reloaded.read_metadata("fresh").unwrap(), Some(metadata("https://a/fresh", 0)));
+
+        // Responses without metadata cannot be aged, so are never used
+        std::fs::write(format!("{}/legacy", location), "{}").unwrap();
+        assert_eq!(reloaded.get("legacy", TTL).unwrap(), None);
+
+        // Whereas pinned responses are used however old they are
+        reloaded.put("pinned", "{\"time\":3}", CacheMetadata { pinned: true, ..metadata("https://a/pinned", 120) }).unwrap();
+        assert_eq!(self::cache(&location).get("pinned", TTL).unwrap(), Some("{\"time\":3}".to_string()));
+
+        std::fs::remove_dir_all(&location).unwrap();
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let location = test_location("eviction");
+        let mut cache = cache(&location).with_limits(CacheLimits { max_memory_bytes: 20, max_disk_bytes: 25 });
+
+        let pause = || std::thread::sleep(Duration::from_millis(20));
+        cache.put("a", "0123456789", metadata("https://a/a", 0)).unwrap();
+        pause();
+        cache.put("b", "0123456789", metadata("https://a/b", 0)).unwrap();
+        pause();
+        assert_eq!(cache.get("a", TTL).unwrap(), Some("0123456789".to_string()));
+        pause();
+
+        // Exceeds both limits, evicting "b" as least recently used
+        cache.put("c", "0123456789", metadata("https://a/c", 0)).unwrap();
+        assert_eq!(cache.data.keys().filter(|&x| x == "b").count(), 0);
+        assert_eq!(cache.memory_used, 20);
+
+        let mut on_disk = std::fs::read_dir(&location).unwrap()
+            .map(|x| x.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+        on_disk.sort();
+        assert_eq!(on_disk, vec!["a", "a.meta", "c", "c.meta"]);
+
+        std::fs::remove_dir_all(&location).unwrap();
+    }
+
+    #[test]
+    fn test_checked_in_snapshot_is_served() {
+        let mut cache: ResponseCache = FilesystemCache::new(String::clone);
+        let raw = cache.get("https---opensky-network-org-api-states-all", TTL).unwrap().unwrap();
+
+        let snapshot = serde_json::from_str::<AircraftData>(&raw).unwrap();
+        assert_eq!(snapshot.time, 1566137050);
+        assert!(!snapshot.data.is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+use std::env;
+use std::fmt;
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::*;
+use crate::sources::httpclient::HttpError;
+
+const CREDENTIALS_FILE: &str = "credentials.json";
+const LEGACY_CREDENTIALS_FILE: &str = "cred";
+
+const ENV_USERNAME: &str = "OPENSKY_USERNAME";
+const ENV_PASSWORD: &str = "OPENSKY_PASSWORD";
+const ENV_CLIENT_ID: &str = "OPENSKY_CLIENT_ID";
+const ENV_CLIENT_SECRET: &str = "OPENSKY_CLIENT_SECRET";
+const ENV_TOKEN_URL: &str = "OPENSKY_TOKEN_URL";
+
+const DEFAULT_TOKEN_URL: &str = "https://auth.opensky-network.org/auth/realms/opensky-network/protocol/openid-connect/token";
+
+// Tokens are renewed this long before they expire, so that no request is made with a stale token
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+const REDACTED: &str = "<redacted>";
+
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Credentials {
+    Basic {
+        username: String,
+        password: String
+    },
+    ClientCredentials {                     // OAuth2 client-credentials grant
+        client_id: String,
+        client_secret: String,
+        #[serde(default = "default_token_url")]
+        token_url: String
+    }
+}
+
+// Supplies authorisation for each request, obtaining and renewing access tokens as required.  Shared
+// between all clients, so that each token is only requested once
+pub struct CredentialProvider {
+    credentials: Credentials,
+    token: Mutex<Option<AccessToken>>
+}
+
+struct AccessToken {
+    value: String,
+    expires_at: Instant
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64                         // Seconds
+}
+
+#[derive(Debug)]
+pub enum CredentialError {
+    File(io::Error),
+    Invalid(String)
+}
+
+impl Credentials {
+    // Reads credentials from the environment, then the credentials file, then the legacy "user:pass@" file
+    pub fn discover() -> Result<Option<Credentials>, CredentialError> {
+        if let Some(x) = Credentials::from_env() {
+            return Ok(Some(x));
+        }
+
+        for (path, parse) in &[(CREDENTIALS_FILE, Credentials::parse as fn(&str) -> Result<Credentials, CredentialError>),
+                               (LEGACY_CREDENTIALS_FILE, Credentials::parse_legacy)] {
+            match std::fs::read_to_string(path) {
+                Ok(x) => return parse(&x).map(Some),
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => return Err(CredentialError::File(e))
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn from_env() -> Option<Credentials> {
+        let var = |name| env::var(name).ok().filter(|x: &String| !x.is_empty());
+
+        if let (Some(client_id), Some(client_secret)) = (var(ENV_CLIENT_ID), var(ENV_CLIENT_SECRET)) {
+            return Some(Credentials::ClientCredentials {
+                client_id, client_secret, token_url: var(ENV_TOKEN_URL).unwrap_or_else(default_token_url)
+            });
+        }
+
+        match (var(ENV_USERNAME), var(ENV_PASSWORD)) {
+            (Some(username), Some(password)) => Some(Credentials::Basic { username, password }),
+            _ => None
+        }
+    }
+
+    // JSON object with either "username" and "password", or "client_id", "client_secret" and optionally "token_url"
+    pub fn parse(config: &str) -> Result<Credentials, CredentialError> {
+        serde_json::from_str(config)
+            .map_err(|_| CredentialError::Invalid(format!("\"{}\" must contain a username and password, or a client_id and client_secret", CREDENTIALS_FILE)))
+    }
+
+    // Previously spliced directly into the request url, in the form "username:password@"
+    fn parse_legacy(config: &str) -> Result<Credentials, CredentialError> {
+        let config = config.trim().trim_end_matches('@');
+        let separator = config.find(':')
+            .ok_or_else(|| CredentialError::Invalid(format!("\"{}\" must be of the form \"username:password@\"", LEGACY_CREDENTIALS_FILE)))?;
+
+        println!("Reading credentials from legacy \"{}\" file; consider moving them to \"{}\"", LEGACY_CREDENTIALS_FILE, CREDENTIALS_FILE);
+        Ok(Credentials::Basic { username: config[..separator].to_string(), password: config[separator + 1..].to_string() })
+    }
+}
+
+impl CredentialProvider {
+    pub fn new(credentials: Credentials) -> Self {
+        Self { credentials, token: Mutex::new(None) }
+    }
+
+    // Adds authorisation to the given request, requesting a new access token first if required
+    pub fn authorise(&self, client: &reqwest::Client, request: reqwest::RequestBuilder) -> Result<reqwest::RequestBuilder, HttpError> {
+        match &self.credentials {
+            Credentials::Basic { username, password } => Ok(request.basic_auth(username, Some(password))),
+            Credentials::ClientCredentials { client_id, client_secret, token_url } => {
+                let mut token = self.token.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+
+                if token.as_ref().map(|x| x.expires_at <= now + TOKEN_REFRESH_MARGIN).unwrap_or(true) {
+                    *token = Some(request_token(client, token_url, client_id, client_secret)?);
+                }
+
+                Ok(request.bearer_auth(&token.as_ref().unwrap_or_else(|| panic!("No access token available")).value))
+            }
+        }
+    }
+
+    // Discards any access token, e.g. if it has been rejected, so that a new one is requested.  Returns
+    // false if the credentials do not use tokens and so cannot be renewed
+    pub fn invalidate(&self) -> bool {
+        match self.credentials {
+            Credentials::Basic { .. } => false,
+            Credentials::ClientCredentials { .. } => {
+                *self.token.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                true
+            }
+        }
+    }
+}
+
+fn request_token(client: &reqwest::Client, token_url: &str, client_id: &str, client_secret: &str) -> Result<AccessToken, HttpError> {
+    println!("Requesting access token for \"{}\"", client_id);
+
+    let mut response = client.post(token_url)
+        .form(&[("grant_type", "client_credentials"), ("client_id", client_id), ("client_secret", client_secret)])
+        .send()?;
+
+    if !response.status().is_success() {
+        return Err(HttpError::Status(response.status().as_u16()));
+    }
+
+    let token: TokenResponse = response.json()?;
+    Ok(AccessToken { value: token.access_token, expires_at: Instant::now() + Duration::from_secs(token.expires_in) })
+}
+
+fn default_token_url() -> String {
+    DEFAULT_TOKEN_URL.to_string()
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Credentials::Basic { username, .. } => f.debug_struct("Basic")
+                .field("username", username)
+                .field("password", &REDACTED)
+                .finish(),
+            Credentials::ClientCredentials { client_id, token_url, .. } => f.debug_struct("ClientCredentials")
+                .field("client_id", client_id)
+                .field("client_secret", &REDACTED)
+                .field("token_url", token_url)
+                .finish()
+        }
+    }
+}
+
+impl fmt::Debug for CredentialProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CredentialProvider")
+            .field("credentials", &self.credentials)
+            .finish()
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AccessToken")
+            .field("value", &REDACTED)
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CredentialError::File(e) => write!(f, "Cannot read credentials ({})", e),
+            CredentialError::Invalid(e) => write!(f, "Invalid credentials ({})", e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Credentials, CredentialProvider};
+    use crate::sources::httpclient::{HttpClient, ClientConfig, mock_server};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_credentials() {
+        assert_eq!(Credentials::parse(r#"{"username": "user", "password": "p@ss:word"}"#).unwrap(),
+                   Credentials::Basic { username: "user".to_string(), password: "p@ss:word".to_string() });
+
+        match Credentials::parse(r#"{"client_id": "id", "client_secret": "secret"}"#).unwrap() {
+            Credentials::ClientCredentials { client_id, token_url, .. } => {
+                assert_eq!(client_id, "id");
+                assert!(token_url.starts_with("https://auth.opensky-network.org/"));
+            },
+            _ => panic!("Expected client credentials")
+        }
+
+        assert!(Credentials::parse(r#"{"username": "user"}"#).is_err());
+        assert_eq!(Credentials::parse_legacy("user:pass@\n").unwrap(),
+                   Credentials::Basic { username: "user".to_string(), password: "pass".to_string() });
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let basic = Credentials::Basic { username: "user".to_string(), password: "hunter2".to_string() };
+        let oauth = Credentials::parse(r#"{"client_id": "id", "client_secret": "hunter2"}"#).unwrap();
+
+        for debug in &[format!("{:?}", basic), format!("{:?}", CredentialProvider::new(oauth))] {
+            assert!(!debug.contains("hunter2"));
+            assert!(debug.contains("<redacted>"));
+        }
+    }
+
+    #[test]
+    fn test_basic_auth_header() {
+        let (url, requests) = mock_server(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+        ]);
+
+        let credentials = Credentials::Basic { username: "user".to_string(), password: "pass".to_string() };
+        let mut client = HttpClient::new(ClientConfig::default()).with_credentials(Arc::new(CredentialProvider::new(credentials)));
+        client.fetch(&url).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert!(request.contains("authorization: Basic dXNlcjpwYXNz"), "{}", request);
+    }
+
+    #[test]
+    fn test_token_reuse_and_refresh() {
+        let (token_url, token_requests) = mock_server(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 42\r\nConnection: close\r\n\r\n{\"access_token\":\"first\",\"expires_in\":1800}",
+            "HTTP/1.1 200 OK\r\nContent-Length: 43\r\nConnection: close\r\n\r\n{\"access_token\":\"second\",\"expires_in\":1800}"
+        ]);
+        let (url, requests) = mock_server(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+            "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+        ]);
+
+        let credentials = Credentials::ClientCredentials { client_id: "id".to_string(), client_secret: "secret".to_string(), token_url };
+        let mut client = HttpClient::new(ClientConfig::default()).with_credentials(Arc::new(CredentialProvider::new(credentials)));
+
+        // Token is reused until rejected, then renewed
+        client.fetch(&url).unwrap();
+        client.fetch(&url).unwrap();
+        client.fetch(&url).unwrap();
+
+        let tokens = requests.try_iter()
+            .map(|x| x.lines().find(|x| x.starts_with("authorization:")).unwrap_or("").to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(tokens, vec!["authorization: Bearer first", "authorization: Bearer first",
+                                "authorization: Bearer first", "authorization: Bearer second"]);
+
+        let token_request = token_requests.recv().unwrap();
+        assert!(token_request.starts_with("POST "));
+        assert_eq!(token_requests.try_iter().count(), 1);
+    }
+}
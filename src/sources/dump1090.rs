@@ -0,0 +1,200 @@
+use serde::*;
+use crate::data::aircraft::{Aircraft, AircraftData, PositionSource};
+use crate::sources::httpclient::{HttpClient, ClientConfig};
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+use crate::util::units::{Altitude, Speed, VerticalRate};
+
+// State vectors read from the `aircraft.json` published by dump1090-fa, readsb/tar1090, and the
+// older dump1090 forks, from either a local file or an HTTP URL
+pub struct Dump1090Source {
+    location: String,
+    client: HttpClient
+}
+
+#[derive(Debug, Deserialize)]
+struct Dump1090Data {
+    now: f64,                               // Time of file generation, as unix timestamp
+    aircraft: Vec<Dump1090Aircraft>
+}
+
+#[derive(Debug, Deserialize)]
+struct Dump1090Aircraft {
+    hex: String,                            // Prefixed with '~' for non-ICAO addresses
+    flight: Option<String>,
+    #[serde(alias = "altitude")]    alt_baro: Option<Dump1090Altitude>,     // Feet
+                                    alt_geom: Option<f32>,                  // Feet
+    #[serde(alias = "speed")]       gs: Option<f32>,                        // Knots
+                                    track: Option<f32>,
+    #[serde(alias = "vert_rate")]   baro_rate: Option<f32>,                 // Feet per minute
+                                    geom_rate: Option<f32>,                 // Feet per minute
+                                    squawk: Option<String>,
+                                    lat: Option<f64>,
+                                    lon: Option<f64>,
+                                    seen: Option<f64>,                      // Seconds since last message
+                                    seen_pos: Option<f64>,                  // Seconds since last position
+    #[serde(default)]               mlat: Vec<String>,                      // Fields derived from MLAT
+    #[serde(default)]               spi: bool
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Dump1090Altitude {
+    Feet(f32),
+    Ground(String)                          // "ground"
+}
+
+impl Dump1090Source {
+    pub fn new(location: &str) -> Self {
+        Self { location: location.to_string(), client: HttpClient::new(ClientConfig::default()) }
+    }
+
+    fn is_remote(&self) -> bool {
+        self.location.starts_with("http://") || self.location.starts_with("https://")
+    }
+
+    fn retrieve(&mut self) -> Result<String, SourceError> {
+        if self.is_remote() {
+            Ok(self.client.get(&self.location)?)
+        } else {
+            std::fs::read_to_string(&self.location).map_err(SourceError::File)
+        }
+    }
+}
+
+impl StateVectorSource for Dump1090Source {
+    fn name(&self) -> String {
+        format!("dump1090 ({})", self.location)
+    }
+
+    fn fetch(&mut self, _query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        Ok(parse_aircraft_json(&self.retrieve()?)?)
+    }
+}
+
+pub fn parse_aircraft_json(data: &str) -> Result<AircraftData, serde_json::error::Error> {
+    let parsed = serde_json::from_str::<Dump1090Data>(data)?;
+    let now = parsed.now;
+
+    Ok(AircraftData {
+        time: now as isize,
+        data: parsed.aircraft.into_iter()
+            .map(|x| x.into_aircraft(now))
+            .collect()
+    })
+}
+
+impl Dump1090Aircraft {
+    fn into_aircraft(self, now: f64) -> Aircraft {
+        let since = |seconds: Option<f64>| seconds.map(|s| (now - s) as i64);
+        // Non-ICAO addresses, e.g. from TIS-B, keep their '~' prefix so that they cannot collide with the ICAO
+        // address of another aircraft.  They are not allocated to any country
+        let mut aircraft = Aircraft::new(&self.hex, since(self.seen).unwrap_or(now as i64));
+
+        let has_position = self.lat.is_some() && self.lon.is_some();
+        if has_position {
+            aircraft.latitude = self.lat;
+            aircraft.longitude = self.lon;
+            aircraft.time_position = since(self.seen_pos).or(Some(aircraft.last_contact));
+        }
+
+        match self.alt_baro {
+            Some(Dump1090Altitude::Feet(ft)) => aircraft.baro_altitude = Some(Altitude::from_feet(ft)),
+            Some(Dump1090Altitude::Ground(x)) => aircraft.on_ground = x == "ground",
+            None => ()
+        }
+
+        aircraft.callsign = self.flight.map(|x| x.trim_end().to_string()).filter(|x| !x.is_empty());
+        aircraft.geo_altitude = self.alt_geom.map(Altitude::from_feet);
+        aircraft.velocity = self.gs.map(Speed::from_knots);
+        aircraft.true_track = self.track;
+        aircraft.vertical_rate = self.baro_rate.or(self.geom_rate).map(VerticalRate::from_feet_per_min);
+        aircraft.squawk = self.squawk.and_then(|x| x.parse().ok());
+        aircraft.spi = self.spi;
+        aircraft.position_source = if has_position && self.mlat.iter().any(|x| x == "lat") {
+            PositionSource::Mlat
+        } else {
+            PositionSource::AdsB
+        };
+
+        aircraft
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_aircraft_json, Dump1090Source};
+    use crate::data::aircraft::{PositionSource, Squawk};
+    use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery};
+
+    const DUMP1090_FA: &str = r#"{ "now" : 1566137050.5, "messages" : 1843, "aircraft" : [
+        {"hex":"4ca2d6","flight":"RYR4TL  ","alt_baro":36000,"alt_geom":36525,"gs":450.5,"track":270.0,
+         "baro_rate":-1024,"squawk":"7700","emergency":"general","category":"A3","lat":53.359210,"lon":-6.271330,
+         "nic":8,"rc":186,"seen_pos":2.5,"version":2,"mlat":[],"tisb":[],"messages":312,"seen":0.5,"rssi":-21.4},
+        {"hex":"406b90","alt_baro":"ground","gs":12.0,"mlat":[],"tisb":[],"messages":5,"seen":12.5,"rssi":-30.1},
+        {"hex":"~2b4c1a","lat":53.1,"lon":-6.0,"alt_baro":2500,"mlat":["lat","lon"],"tisb":[],"seen_pos":1.0,"seen":1.0}
+    ]}"#;
+
+    const DUMP1090_MUTABILITY: &str = r#"{ "now" : 1566137050.0, "messages" : 95, "aircraft" : [
+        {"hex":"ac96b8","squawk":"0755","flight":"AAL137  ","lat":32.9235,"lon":-97.0546,"nucp":7,"seen_pos":0.3,
+         "altitude":750,"vert_rate":-960,"track":180,"speed":140,"mlat":[],"tisb":[],"messages":220,"seen":0.2,"rssi":-18.0}
+    ]}"#;
+
+    #[test]
+    fn test_parse_dump1090_fa() {
+        let data = parse_aircraft_json(DUMP1090_FA).unwrap();
+        assert_eq!(data.time, 1566137050);
+        assert_eq!(data.data.len(), 3);
+
+        let a = &data.data[0];
+        assert_eq!(a.icao24, "4ca2d6");
+        assert_eq!(a.origin_country, "Ireland");
+        assert_eq!(a.callsign, Some("RYR4TL".to_string()));
+        assert_eq!((a.latitude, a.longitude), (Some(53.35921), Some(-6.27133)));
+        assert_eq!(a.time_position, Some(1566137048));
+        assert_eq!(a.last_contact, 1566137050);
+        assert!((a.baro_altitude.unwrap().metres() - 10972.8).abs() < 0.01);
+        assert!((a.geo_altitude.unwrap().metres() - 11132.82).abs() < 0.01);
+        assert!((a.velocity.unwrap().metres_per_sec() - 231.76).abs() < 0.01);
+        assert!((a.vertical_rate.unwrap().metres_per_sec() - -5.2019).abs() < 0.001);
+        assert_eq!(a.true_track, Some(270.0));
+        assert_eq!(a.squawk, Some(Squawk::EMERGENCY));
+        assert_eq!(a.position_source, PositionSource::AdsB);
+        assert!(!a.on_ground);
+
+        let b = &data.data[1];
+        assert!(b.on_ground);
+        assert_eq!(b.baro_altitude, None);
+        assert_eq!(b.latitude, None);
+        assert_eq!(b.time_position, None);
+        assert_eq!(b.last_contact, 1566137038);
+
+        let c = &data.data[2];
+        assert_eq!(c.icao24, "~2b4c1a");
+        assert_eq!(c.origin_country, "");
+        assert_eq!(c.position_source, PositionSource::Mlat);
+    }
+
+    #[test]
+    fn test_parse_dump1090_mutability() {
+        let data = parse_aircraft_json(DUMP1090_MUTABILITY).unwrap();
+        let a = &data.data[0];
+        assert_eq!(a.callsign, Some("AAL137".to_string()));
+        assert!((a.baro_altitude.unwrap().metres() - 228.6).abs() < 0.01);
+        assert!((a.velocity.unwrap().metres_per_sec() - 72.02).abs() < 0.01);
+        assert!((a.vertical_rate.unwrap().metres_per_sec() - -4.8768).abs() < 0.001);
+        assert_eq!(a.true_track, Some(180.0));
+    }
+
+    #[test]
+    fn test_read_from_file() {
+        let path = std::env::temp_dir().join(format!("flight-radar-aircraft-{}.json", std::process::id()));
+        std::fs::write(&path, DUMP1090_FA).unwrap();
+
+        let mut source = Dump1090Source::new(path.to_str().unwrap());
+        let data = source.fetch(&StateVectorQuery::default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(data.unwrap().data.len(), 3);
+        assert!(Dump1090Source::new("no-such-file.json").fetch(&StateVectorQuery::default()).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::data::aircraft::{Aircraft, AircraftData};
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+
+// Hold-off for a rate-limited member which does not say for how long
+const DEFAULT_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+
+// Combines several sources into a single view, merging the state vectors for each aircraft so that
+// every field comes from whichever source has the freshest data for it
+pub struct FusionSource {
+    members: Vec<Member>
+}
+
+struct Member {
+    source: Box<dyn StateVectorSource>,
+    name: String,
+    held_until: Option<Instant>             // Rate-limited members are skipped, without holding up the others
+}
+
+impl FusionSource {
+    pub fn new(sources: Vec<Box<dyn StateVectorSource>>) -> Self {
+        Self {
+            members: sources.into_iter()
+                .map(|source| Member { name: source.name(), source, held_until: None })
+                .collect()
+        }
+    }
+}
+
+impl StateVectorSource for FusionSource {
+    fn name(&self) -> String {
+        format!("Fusion of {}", self.members.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>().join(", "))
+    }
+
+    // Succeeds if any member can supply data; failures of individual members are reported and skipped
+    fn fetch(&mut self, query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        let now = Instant::now();
+        let mut snapshots = vec![];
+        let mut last_error = None;
+
+        for member in self.members.iter_mut().filter(|x| x.held_until.map(|t| t <= now).unwrap_or(true)) {
+            match member.source.fetch(query) {
+                Ok(data) => snapshots.push((member.name.as_str(), data)),
+                Err(e) => {
+                    eprintln!("Failed to retrieve data from {} ({})", member.name, e);
+                    if let SourceError::RateLimited(delay) = &e {
+                        member.held_until = Some(now + delay.unwrap_or(DEFAULT_RATE_LIMIT_DELAY));
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match (snapshots.is_empty(), last_error) {
+            (true, Some(e)) => Err(e),
+            _ => Ok(merge(snapshots))
+        }
+    }
+}
+
+// Merges snapshots from several sources into one, attributing each aircraft to the source of its position
+fn merge(snapshots: Vec<(&str, AircraftData)>) -> AircraftData {
+    let time = snapshots.iter().map(|(_, x)| x.time).max().unwrap_or(0);
+    let mut merged: HashMap<String, Aircraft> = HashMap::new();
+    let mut order: Vec<String> = vec![];
+
+    for (name, snapshot) in snapshots {
+        for mut aircraft in snapshot.data {
+            aircraft.data_source = Some(name.to_string());
+
+            match merged.get_mut(&aircraft.icao24) {
+                Some(existing) => merge_state_vector(existing, aircraft),
+                None => {
+                    order.push(aircraft.icao24.clone());
+                    merged.insert(aircraft.icao24.clone(), aircraft);
+                }
+            }
+        }
+    }
+
+    AircraftData {
+        time,
+        data: order.iter().filter_map(|x| merged.remove(x)).collect()
+    }
+}
+
+// Position-related fields are taken together from whichever source has the latest position, since
+// they are reported together; all other fields from the latest contact, falling back to any source
+// which has a value
+fn merge_state_vector(existing: &mut Aircraft, other: Aircraft) {
+    if other.time_position > existing.time_position {
+        existing.time_position = other.time_position;
+        existing.longitude = other.longitude;
+        existing.latitude = other.latitude;
+        existing.baro_altitude = other.baro_altitude.or(existing.baro_altitude);
+        existing.geo_altitude = other.geo_altitude.or(existing.geo_altitude);
+        existing.on_ground = other.on_ground;
+        existing.position_source = other.position_source;
+        existing.data_source = other.data_source;
+    }
+
+    let newer = other.last_contact > existing.last_contact;
+    freshest(&mut existing.callsign, other.callsign, newer);
+    freshest(&mut existing.squawk, other.squawk, newer);
+    freshest(&mut existing.sensors, other.sensors, newer);
+    freshest(&mut existing.velocity, other.velocity, newer);
+    freshest(&mut existing.true_track, other.true_track, newer);
+    freshest(&mut existing.vertical_rate, other.vertical_rate, newer);
+
+    if newer {
+        existing.last_contact = other.last_contact;
+        existing.spi = other.spi;
+    }
+    if existing.origin_country.is_empty() {
+        existing.origin_country = other.origin_country;
+    }
+}
+
+fn freshest<T>(existing: &mut Option<T>, other: Option<T>, newer: bool) {
+    if other.is_some() && (newer || existing.is_none()) {
+        *existing = other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FusionSource, merge};
+    use crate::data::aircraft::{Aircraft, AircraftData};
+    use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+    use crate::util::units::Speed;
+
+    struct FixtureSource {
+        name: &'static str,
+        data: Option<AircraftData>
+    }
+
+    impl StateVectorSource for FixtureSource {
+        fn name(&self) -> String { self.name.to_string() }
+
+        fn fetch(&mut self, _query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+            self.data.clone().ok_or(SourceError::RateLimited(None))
+        }
+    }
+
+    fn aircraft(icao24: &str, time_position: Option<i64>, last_contact: i64, position: (f64, f64)) -> Aircraft {
+        Aircraft { time_position, longitude: Some(position.0), latitude: Some(position.1), ..Aircraft::new(icao24, last_contact) }
+    }
+
+    #[test]
+    fn test_merge_by_freshest_fields() {
+        let remote = Aircraft {
+            callsign: Some("BAW123  ".to_string()), origin_country: "United Kingdom".to_string(), velocity: Some(Speed::from_metres_per_sec(200.0)),
+            ..aircraft("4ca2d6", Some(990), 995, (-1.0, 51.0))
+        };
+        let local = Aircraft {
+            velocity: Some(Speed::from_metres_per_sec(210.0)),
+            ..aircraft("4ca2d6", Some(1000), 1000, (-1.1, 51.1))
+        };
+        let other = aircraft("406b90", Some(1000), 1000, (0.0, 50.0));
+
+        let merged = merge(vec![
+            ("OpenSky", AircraftData { time: 995, data: vec![remote, other] }),
+            ("Beast", AircraftData { time: 1000, data: vec![local] })
+        ]);
+
+        assert_eq!(merged.time, 1000);
+        assert_eq!(merged.data.len(), 2);
+
+        let fused = &merged.data[0];
+        assert_eq!((fused.longitude, fused.latitude, fused.time_position), (Some(-1.1), Some(51.1), Some(1000)));
+        assert_eq!(fused.velocity, Some(Speed::from_metres_per_sec(210.0)));
+        assert_eq!(fused.callsign.as_deref(), Some("BAW123  "));
+        assert_eq!(fused.origin_country, "United Kingdom");
+        assert_eq!(fused.last_contact, 1000);
+        assert_eq!(fused.data_source.as_deref(), Some("Beast"));
+        assert_eq!(merged.data[1].data_source.as_deref(), Some("OpenSky"));
+    }
+
+    #[test]
+    fn test_stale_position_is_not_applied() {
+        let merged = merge(vec![
+            ("Beast", AircraftData { time: 1000, data: vec![aircraft("4ca2d6", Some(1000), 1000, (-1.1, 51.1))] }),
+            ("OpenSky", AircraftData { time: 1000, data: vec![aircraft("4ca2d6", Some(990), 999, (-1.0, 51.0))] })
+        ]);
+
+        assert_eq!(merged.data[0].longitude, Some(-1.1));
+        assert_eq!(merged.data[0].data_source.as_deref(), Some("Beast"));
+    }
+
+    #[test]
+    fn test_failed_members_are_skipped() {
+        let mut fusion = FusionSource::new(vec![
+            Box::new(FixtureSource { name: "Limited", data: None }),
+            Box::new(FixtureSource { name: "Local", data: Some(AircraftData { time: 1000, data: vec![Aircraft::new("4ca2d6", 1000)] }) })
+        ]);
+
+        let data = fusion.fetch(&StateVectorQuery::default()).unwrap();
+        assert_eq!(data.data.len(), 1);
+        assert!(fusion.members[0].held_until.is_some());
+
+        let mut failed = FusionSource::new(vec![Box::new(FixtureSource { name: "Limited", data: None })]);
+        assert!(failed.fetch(&StateVectorQuery::default()).is_err());
+    }
+}
@@ -1,6 +1,266 @@
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use rand::Rng;
 use reqwest;
+use crate::sources::credentials::CredentialProvider;
 
-pub fn get(url: &str) -> Result<String, reqwest::Error> {
-    Ok(reqwest::get(url)?.text()?)
+const HEADER_RATE_LIMIT_REMAINING: &str = "X-Rate-Limit-Remaining";
+const HEADER_RATE_LIMIT_RETRY_AFTER: &str = "X-Rate-Limit-Retry-After-Seconds";
+const STATUS_UNAUTHORIZED: u16 = 401;
+const STATUS_TOO_MANY_REQUESTS: u16 = 429;
+
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub connect_timeout: Duration,
+    pub timeout: Duration,                  // For the entire request, including reading the response
+    pub max_retries: u32,
+    pub initial_backoff: Duration,          // Doubled after each failed attempt, with jitter
+    pub max_backoff: Duration               // Longest we will wait before a retry, including when rate-limited
+}
+
+// Request quota reported by the server in its most recent response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimit {
+    pub remaining: Option<u32>,
+    pub retry_after: Option<Duration>
+}
+
+// A successful response
+pub struct Response {
+    pub status: u16,
+    pub body: String
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    Request(reqwest::Error),                // Connection failures, timeouts and invalid requests
+    Status(u16),                            // Any unsuccessful response other than rate-limiting
+    RateLimited(Option<Duration>)           // Server has refused the request; retry after this delay, if known
 }
 
+pub struct HttpClient {
+    client: reqwest::Client,
+    config: ClientConfig,
+    credentials: Option<Arc<CredentialProvider>>,
+    rate_limit: RateLimit
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(20),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30)
+        }
+    }
+}
+
+impl HttpClient {
+    pub fn new(config: ClientConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.timeout)
+            .build()
+            .unwrap_or_else(|e| panic!("Cannot initialise HTTP client ({})", e));
+
+        Self { client, config, credentials: None, rate_limit: RateLimit::default() }
+    }
+
+    pub fn with_credentials(self, credentials: Arc<CredentialProvider>) -> Self {
+        Self { credentials: Some(credentials), ..self }
+    }
+
+    pub fn rate_limit(&self) -> &RateLimit {
+        &self.rate_limit
+    }
+
+    pub fn get(&mut self, url: &str) -> Result<String, HttpError> {
+        Ok(self.fetch(url)?.body)
+    }
+
+    // Retrieves the given url, retrying transient failures with exponential backoff.  Rate-limited
+    // requests are retried after the delay requested by the server, unless that exceeds our own
+    // maximum backoff, in which case the caller is expected to hold off instead
+    pub fn fetch(&mut self, url: &str) -> Result<Response, HttpError> {
+        let mut attempt = 0;
+        loop {
+            let error = match self.attempt(url) {
+                Ok(response) => return Ok(response),
+                Err(e) => e
+            };
+
+            let delay = match &error {
+                HttpError::Status(STATUS_UNAUTHORIZED) if attempt == 0 && self.invalidate_credentials() => Some(Duration::from_secs(0)),
+                HttpError::RateLimited(Some(delay)) if *delay > self.config.max_backoff => None,
+                HttpError::RateLimited(Some(delay)) => Some(*delay),
+                HttpError::Status(status) if *status < 500 => None,
+                _ => Some(self.backoff(attempt))
+            };
+
+            match delay {
+                Some(delay) if attempt < self.config.max_retries => {
+                    eprintln!("Request failed ({}), retrying in {:.1}s", error, delay.as_secs_f64());
+                    thread::sleep(delay);
+                    attempt += 1;
+                },
+                _ => return Err(error)
+            }
+        }
+    }
+
+    fn attempt(&mut self, url: &str) -> Result<Response, HttpError> {
+        let request = match &self.credentials {
+            Some(credentials) => credentials.authorise(&self.client, self.client.get(url))?,
+            None => self.client.get(url)
+        };
+        let mut response = request.send()?;
+
+        let header = |name| response.headers().get(name)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.trim().parse::<u64>().ok());
+        self.rate_limit = RateLimit {
+            remaining: header(HEADER_RATE_LIMIT_REMAINING).map(|x| x as u32),
+            retry_after: header(HEADER_RATE_LIMIT_RETRY_AFTER).map(Duration::from_secs)
+        };
+
+        let status = response.status().as_u16();
+        match status {
+            STATUS_TOO_MANY_REQUESTS => Err(HttpError::RateLimited(self.rate_limit.retry_after)),
+            200..=299 => Ok(Response { status, body: response.text()? }),
+            _ => Err(HttpError::Status(status))
+        }
+    }
+
+    // Discards any rejected access token so that the request can be retried with a new one
+    fn invalidate_credentials(&self) -> bool {
+        self.credentials.as_ref()
+            .map(|x| x.invalidate())
+            .unwrap_or(false)
+    }
+
+    // Exponential backoff with "equal jitter", i.e. a random delay between half and all of the nominal value
+    fn backoff(&self, attempt: u32) -> Duration {
+        let nominal = self.config.initial_backoff
+            .checked_mul(1 << attempt.min(16))
+            .unwrap_or(self.config.max_backoff)
+            .min(self.config.max_backoff);
+
+        nominal.mul_f64(rand::thread_rng().gen_range(0.5, 1.0))
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::Request(e) => write!(f, "{}", e),
+            HttpError::Status(status) => write!(f, "HTTP status {}", status),
+            HttpError::RateLimited(Some(delay)) => write!(f, "Rate limited for {}s", delay.as_secs()),
+            HttpError::RateLimited(None) => write!(f, "Rate limited")
+        }
+    }
+}
+
+impl From<reqwest::Error> for HttpError {
+    fn from(error: reqwest::Error) -> HttpError {
+        HttpError::Request(error)
+    }
+}
+
+// Serves each of the given raw responses in turn, one per connection, reporting the head of each request received
+#[cfg(test)]
+pub fn mock_server(responses: Vec<&'static str>) -> (String, std::sync::mpsc::Receiver<String>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}/states/all", listener.local_addr().unwrap());
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    thread::spawn(move || {
+        for (stream, response) in listener.incoming().zip(responses) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            // Read the request line and headers, then any body, before responding
+            let mut head = String::new();
+            while reader.read_line(&mut head).map(|n| n > 2).unwrap_or(false) { }
+
+            let content_length = head.lines()
+                .filter_map(|x| x.to_ascii_lowercase().strip_prefix("content-length:").map(|n| n.trim().parse::<usize>().unwrap_or(0)))
+                .next()
+                .unwrap_or(0);
+            reader.read_exact(&mut vec![0u8; content_length]).unwrap();
+
+            tx.send(head.trim().to_string()).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    (address, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HttpClient, ClientConfig, HttpError, mock_server};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    fn test_config() -> ClientConfig {
+        ClientConfig { initial_backoff: Duration::from_millis(1), max_backoff: Duration::from_secs(2), ..ClientConfig::default() }
+    }
+
+    #[test]
+    fn test_retry_after_failures() {
+        let (url, requests) = mock_server(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 429 Too Many Requests\r\nX-Rate-Limit-Retry-After-Seconds: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nX-Rate-Limit-Remaining: 42\r\nContent-Length: 16\r\nConnection: close\r\n\r\n{\"time\":1000000}"
+        ]);
+
+        let mut client = HttpClient::new(test_config());
+        let response = client.fetch(&url).unwrap();
+
+        assert_eq!(response.body, "{\"time\":1000000}");
+        assert_eq!(client.rate_limit().remaining, Some(42));
+        assert_eq!(requests.try_iter().map(|x| x.lines().next().unwrap().to_string()).collect::<Vec<String>>(),
+                   vec!["GET /states/all HTTP/1.1"; 3]);
+    }
+
+    #[test]
+    fn test_long_rate_limit_is_not_waited_out() {
+        let (url, requests) = mock_server(vec![
+            "HTTP/1.1 429 Too Many Requests\r\nX-Rate-Limit-Retry-After-Seconds: 3600\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ]);
+
+        let mut client = HttpClient::new(test_config());
+        match client.fetch(&url) {
+            Err(HttpError::RateLimited(Some(delay))) => assert_eq!(delay, Duration::from_secs(3600)),
+            _ => panic!("Expected rate-limiting error")
+        }
+        assert_eq!(requests.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_client_errors_are_not_retried() {
+        let (url, requests) = mock_server(vec![
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ]);
+
+        let mut client = HttpClient::new(test_config());
+        assert!(matches!(client.fetch(&url), Err(HttpError::Status(404))));
+        assert_eq!(requests.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_request_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/states/all", listener.local_addr().unwrap());
+
+        let mut client = HttpClient::new(ClientConfig { timeout: Duration::from_millis(100), max_retries: 1, ..test_config() });
+        assert!(matches!(client.fetch(&url), Err(HttpError::Request(_))));
+        drop(listener);
+    }
+}
@@ -1,3 +1,13 @@
 pub mod sources;
 pub mod httpclient;
-pub mod caching;
\ No newline at end of file
+pub mod credentials;
+pub mod caching;
+pub mod state_vectors;
+pub mod opensky;
+pub mod receiver;
+pub mod basestation;
+pub mod beast;
+pub mod avr;
+pub mod dump1090;
+pub mod recording;
+pub mod fusion;
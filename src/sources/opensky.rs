@@ -0,0 +1,41 @@
+use crate::data::aircraft::AircraftData;
+use crate::sources::caching::{self, ResponseCache};
+use crate::sources::httpclient::{HttpClient, RateLimit};
+use crate::sources::sources::{Source, SourceProvider};
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+
+// State vectors retrieved from the OpenSky `/states/all` REST endpoint, or its cached equivalent
+pub struct OpenSkySource {
+    provider: SourceProvider,
+    cache: ResponseCache,
+    client: HttpClient
+}
+
+impl OpenSkySource {
+    pub fn new(provider: SourceProvider) -> Self {
+        let client = provider.client();
+        Self { provider, cache: caching::response_cache(), client }
+    }
+
+    fn source(&self, query: &StateVectorQuery) -> Source {
+        query.bounds
+            .map(|bounds| self.provider.source_state_vectors_bounded(&bounds))
+            .unwrap_or_else(|| self.provider.source_state_vectors())
+    }
+}
+
+impl StateVectorSource for OpenSkySource {
+    fn name(&self) -> String {
+        "OpenSky".to_string()
+    }
+
+    fn fetch(&mut self, query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        let source = self.source(query);
+        let data = caching::retrieve(&mut self.cache, &mut self.client, &source)?;
+        Ok(serde_json::from_str(data.as_str())?)
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(self.client.rate_limit().clone())
+    }
+}
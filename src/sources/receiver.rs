@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::TcpStream;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+use crate::data::aircraft::{Aircraft, AircraftData};
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+const READ_BUFFER_SIZE: usize = 4096;
+
+pub const DEFAULT_STALE_AFTER_SECS: i64 = 60;
+
+// Aircraft state built up incrementally from the messages of a local receiver, keyed by icao24
+pub struct ReceiverState {
+    aircraft: HashMap<String, Aircraft>,
+    stale_after: i64                        // Aircraft not heard from within this many seconds are dropped
+}
+
+impl ReceiverState {
+    pub fn new(stale_after: i64) -> Self {
+        Self { aircraft: HashMap::new(), stale_after }
+    }
+
+    // Applies an update to the given aircraft, creating it if this is the first contact
+    pub fn update<F>(&mut self, icao24: &str, time: i64, update: F)
+        where F: FnOnce(&mut Aircraft) {
+        let aircraft = self.aircraft
+            .entry(icao24.to_lowercase())
+            .or_insert_with(|| Aircraft::new(icao24, time));
+
+        aircraft.last_contact = aircraft.last_contact.max(time);
+        update(aircraft);
+    }
+
+    pub fn get(&self, icao24: &str) -> Option<&Aircraft> {
+        self.aircraft.get(icao24)
+    }
+
+    // Returns all current aircraft, after discarding any which have not been heard from recently
+    pub fn snapshot(&mut self, time: i64) -> AircraftData {
+        let stale_after = self.stale_after;
+        self.aircraft.retain(|_, x| time - x.last_contact <= stale_after);
+
+        AircraftData {
+            time: time as isize,
+            data: self.aircraft.values().cloned().collect()
+        }
+    }
+}
+
+// Maintains a connection to the given TCP feed, forwarding all raw data received.  Will reconnect
+// as required, and runs until the receiving end of the channel is dropped
+pub fn spawn_tcp_feed(address: String, out: Sender<Vec<u8>>) {
+    thread::spawn(move || loop {
+        match TcpStream::connect(&address) {
+            Ok(mut stream) => {
+                println!("Connected to receiver feed at {}", address);
+                let mut buffer = [0u8; READ_BUFFER_SIZE];
+                loop {
+                    match stream.read(&mut buffer) {
+                        Ok(0) => { eprintln!("Receiver feed at {} closed the connection", address); break },
+                        Ok(n) => if out.send(buffer[..n].to_vec()).is_err() { return },
+                        Err(e) => { eprintln!("Receiver feed error from {} ({})", address, e); break }
+                    }
+                }
+            },
+            Err(e) => eprintln!("Cannot connect to receiver feed at {} ({})", address, e)
+        }
+
+        thread::sleep(RECONNECT_INTERVAL);
+    });
+}
+
+// Splits a byte stream into complete lines, retaining any partial line until more data arrives
+#[derive(Default)]
+pub struct LineBuffer {
+    pending: Vec<u8>
+}
+
+impl LineBuffer {
+    pub fn new() -> Self {
+        Self { pending: vec![] }
+    }
+
+    pub fn push(&mut self, data: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(data);
+
+        let complete = match self.pending.iter().rposition(|&b| b == b'\n') {
+            Some(ix) => self.pending.drain(..=ix).collect::<Vec<u8>>(),
+            None => return vec![]
+        };
+
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+}
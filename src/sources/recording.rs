@@ -0,0 +1,191 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use crate::data::aircraft::AircraftData;
+use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError, Timeline};
+use crate::sources::httpclient::RateLimit;
+
+// Appends each snapshot to a session file, one JSON object per line in the OpenSky `/states/all`
+// layout, so that any session can be replayed or inspected with standard tools
+pub struct SessionRecorder {
+    path: String,
+    file: File
+}
+
+// Wraps any other source, recording every snapshot it successfully retrieves
+pub struct RecordingSource {
+    inner: Box<dyn StateVectorSource>,
+    recorder: SessionRecorder
+}
+
+// Replays a recorded session, presenting whichever snapshot was current at the requested
+// simulation time.  Playback rate and position are driven by the simulation clock
+pub struct ReplaySource {
+    path: String,
+    snapshots: Vec<AircraftData>
+}
+
+impl SessionRecorder {
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { path: path.to_string(), file })
+    }
+
+    pub fn record(&mut self, data: &AircraftData) -> std::io::Result<()> {
+        let line = serde_json::to_string(data)?;
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()
+    }
+}
+
+impl RecordingSource {
+    pub fn new(inner: Box<dyn StateVectorSource>, recorder: SessionRecorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl StateVectorSource for RecordingSource {
+    fn name(&self) -> String {
+        format!("{}, recording to \"{}\"", self.inner.name(), self.recorder.path)
+    }
+
+    fn fetch(&mut self, query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        let data = self.inner.fetch(query)?;
+        self.recorder.record(&data)
+            .unwrap_or_else(|e| eprintln!("Failed to record snapshot to \"{}\" ({})", self.recorder.path, e));
+
+        Ok(data)
+    }
+
+    fn timeline(&self) -> Option<Timeline> {
+        self.inner.timeline()
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        self.inner.rate_limit()
+    }
+}
+
+impl ReplaySource {
+    pub fn load(path: &str) -> Result<Self, SourceError> {
+        let file = File::open(path).map_err(SourceError::File)?;
+
+        let mut snapshots = BufReader::new(file).lines()
+            .enumerate()
+            .map(|(i, line)| line.map(|x| (i, x)))
+            .collect::<std::io::Result<Vec<(usize, String)>>>()
+            .map_err(SourceError::File)?
+            .iter()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(i, line)| serde_json::from_str::<AircraftData>(line)
+                .map_err(|e| eprintln!("Skipping invalid snapshot at line {} of \"{}\" ({})", i + 1, path, e))
+                .ok())
+            .collect::<Vec<AircraftData>>();
+
+        snapshots.sort_by_key(|x| x.time);
+        println!("Loaded {} snapshots from \"{}\"", snapshots.len(), path);
+
+        Ok(Self::new(path, snapshots))
+    }
+
+    pub fn new(path: &str, snapshots: Vec<AircraftData>) -> Self {
+        Self { path: path.to_string(), snapshots }
+    }
+
+    // Latest snapshot recorded at or before the given time, or the first snapshot if none
+    pub fn snapshot_at(&self, time: isize) -> Option<&AircraftData> {
+        let count = self.snapshots.iter().take_while(|x| x.time <= time).count();
+        self.snapshots.get(count.max(1) - 1)
+    }
+}
+
+impl StateVectorSource for ReplaySource {
+    fn name(&self) -> String {
+        format!("Replay of \"{}\"", self.path)
+    }
+
+    // Queries without a time are served from the start of the session
+    fn fetch(&mut self, query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+        Ok(self.snapshot_at(query.time.unwrap_or(isize::MIN))
+            .cloned()
+            .unwrap_or_else(AircraftData::empty))
+    }
+
+    fn timeline(&self) -> Option<Timeline> {
+        Some(Timeline::new(self.snapshots.iter().map(|x| x.time).collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionRecorder, RecordingSource, ReplaySource};
+    use crate::data::aircraft::{Aircraft, AircraftData};
+    use crate::sources::state_vectors::{StateVectorSource, StateVectorQuery, SourceError};
+
+    // Test fixture producing a fixed sequence of snapshots
+    struct FixtureSource {
+        snapshots: Vec<AircraftData>
+    }
+
+    impl StateVectorSource for FixtureSource {
+        fn name(&self) -> String { "Fixture".to_string() }
+
+        fn fetch(&mut self, _query: &StateVectorQuery) -> Result<AircraftData, SourceError> {
+            Ok(self.snapshots.remove(0))
+        }
+    }
+
+    fn snapshot(time: isize, icao24: &[&str]) -> AircraftData {
+        AircraftData {
+            time,
+            data: icao24.iter().map(|x| Aircraft::new(x, time as i64)).collect()
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay_session() {
+        let path = std::env::temp_dir().join(format!("flight-radar-session-{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut source = RecordingSource::new(
+            Box::new(FixtureSource { snapshots: vec![snapshot(1000, &["4ca2d6"]), snapshot(1002, &["4ca2d6", "406b90"])] }),
+            SessionRecorder::create(path).unwrap());
+
+        let query = StateVectorQuery::default();
+        source.fetch(&query).unwrap();
+        source.fetch(&query).unwrap();
+
+        let replay = ReplaySource::load(path);
+        std::fs::remove_file(path).unwrap();
+        let replay = replay.unwrap();
+
+        assert_eq!(replay.snapshots.len(), 2);
+        assert_eq!(replay.snapshots[1].time, 1002);
+        assert_eq!(replay.snapshots[1].data[1].icao24, "406b90");
+        assert_eq!(replay.snapshots[1].data[1].last_contact, 1002);
+    }
+
+    #[test]
+    fn test_replay_snapshot_selection() {
+        let replay = ReplaySource::new("test", vec![snapshot(1000, &["a"]), snapshot(1002, &["b"]), snapshot(1010, &["c"])]);
+
+        assert_eq!(replay.snapshot_at(900).unwrap().time, 1000);
+        assert_eq!(replay.snapshot_at(1000).unwrap().time, 1000);
+        assert_eq!(replay.snapshot_at(1009).unwrap().time, 1002);
+        assert_eq!(replay.snapshot_at(2000).unwrap().time, 1010);
+        assert!(ReplaySource::new("test", vec![]).snapshot_at(1000).is_none());
+    }
+
+    #[test]
+    fn test_replay_timeline() {
+        let mut replay = ReplaySource::new("test", vec![snapshot(1000, &["a"]), snapshot(1002, &["b"]), snapshot(1060, &["c"])]);
+        assert_eq!(replay.fetch(&StateVectorQuery::default()).unwrap().time, 1000);
+        assert_eq!(replay.fetch(&StateVectorQuery { time: Some(1059), ..Default::default() }).unwrap().time, 1002);
+
+        let timeline = replay.timeline().unwrap();
+        assert_eq!((timeline.start(), timeline.end()), (1000, 1060));
+        assert_eq!(timeline.step(1001, 1), Some(1002));
+        assert_eq!(timeline.step(1001, -1), Some(1000));
+        assert_eq!(timeline.step(1002, 5), Some(1060));
+        assert_eq!(timeline.step(900, -1), Some(1000));
+    }
+}
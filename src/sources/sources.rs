@@ -1,65 +1,96 @@
-const CRED_TOKEN: &str = "{CRED}";
+use std::sync::Arc;
+use std::time::Duration;
+use crate::geo::bounds::GeoBounds;
+use crate::sources::credentials::CredentialProvider;
+use crate::sources::httpclient::{HttpClient, ClientConfig};
 
+const API_ROOT: &str = "https://opensky-network.org/api";
+
+// Maximum age of cached responses for each endpoint, in line with how often its content changes
+const STATE_VECTORS_TTL: Duration = Duration::from_secs(10);
+const FLIGHT_DATA_TTL: Duration = Duration::from_secs(30 * 60);
+const TRACK_TTL: Duration = Duration::from_secs(60);
+const AIRPORT_FLIGHTS_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone)]
 pub struct SourceProvider {
-    cred: Option<String>,
+    credentials: Option<Arc<CredentialProvider>>,
     use_cache: bool
 }
 
 #[derive(Debug)]
 pub struct Source {
     path: String,
-    cred: Option<String>,
-    use_cache: bool
+    use_cache: bool,
+    cache_ttl: Duration
 }
 
 impl SourceProvider {
-    pub fn new(cred: &Option<String>, use_cache: bool) -> Self {
-        Self { cred: cred.clone(), use_cache }
+    pub fn new(credentials: Option<CredentialProvider>, use_cache: bool) -> Self {
+        Self { credentials: credentials.map(Arc::new), use_cache }
     }
 
     pub fn _should_use_cache(&self) -> bool { return self.use_cache; }  // @Unused
-    pub fn is_authenticated(&self) -> bool { return self.cred.is_some() }
+    pub fn is_authenticated(&self) -> bool { return self.credentials.is_some() }
+
+    // Client for retrieving any of these sources, authenticated if credentials are available
+    pub fn client(&self) -> HttpClient {
+        let client = HttpClient::new(ClientConfig::default());
+        match &self.credentials {
+            Some(credentials) => client.with_credentials(credentials.clone()),
+            None => client
+        }
+    }
 
-    fn source(&self, path: String) -> Source {
-        Source::new(format!("https://{}opensky-network.org/api{}", CRED_TOKEN, path),
-                    self.cred.clone(), self.use_cache)
+    fn source(&self, path: String, cache_ttl: Duration) -> Source {
+        Source::new(format!("{}{}", API_ROOT, path), self.use_cache, cache_ttl)
     }
 
     pub fn source_state_vectors(&self) -> Source {
-        self.source("/states/all".to_string())
+        self.source("/states/all".to_string(), STATE_VECTORS_TTL)
+    }
+
+    pub fn source_state_vectors_bounded(&self, bounds: &GeoBounds) -> Source {
+        self.source(format!("/states/all?lamin={:.4}&lomin={:.4}&lamax={:.4}&lomax={:.4}",
+                            bounds.lat_min, bounds.lon_min, bounds.lat_max, bounds.lon_max), STATE_VECTORS_TTL)
     }
 
     pub fn source_flight_data(&self, icao24: &String, start: i64, end: i64) -> Source {
-        self.source(format!("/flights/aircraft?icao24={}&begin={}&end={}", icao24, start, end))
+        self.source(format!("/flights/aircraft?icao24={}&begin={}&end={}", icao24, start, end), FLIGHT_DATA_TTL)
     }
-}
 
-impl Source {
-    pub fn new(path: String, cred: Option<String>, use_cache: bool) -> Self {
-        Self { path, cred, use_cache }
+    // Flights which arrived at the given airport (ICAO identifier) within the time window; at most seven days
+    pub fn source_airport_arrivals(&self, airport: &str, start: i64, end: i64) -> Source {
+        self.source(format!("/flights/arrival?airport={}&begin={}&end={}", airport, start, end), AIRPORT_FLIGHTS_TTL)
     }
 
-    fn resolve_path(&self, cred: &Option<String>) -> String {
-        return self.path
-            .replace(CRED_TOKEN,
-                cred.as_ref().map(|s| s.as_str()).unwrap_or_else(|| "")
-            )
+    // Flights which departed from the given airport (ICAO identifier) within the time window; at most seven days
+    pub fn source_airport_departures(&self, airport: &str, start: i64, end: i64) -> Source {
+        self.source(format!("/flights/departure?airport={}&begin={}&end={}", airport, start, end), AIRPORT_FLIGHTS_TTL)
     }
 
-    pub fn get_path(&self) -> String {
-        self.resolve_path(&self.cred)
+    // Time may be any point within the flight of interest, or zero to retrieve the live track
+    pub fn source_track(&self, icao24: &String, time: i64) -> Source {
+        self.source(format!("/tracks/all?icao24={}&time={}", icao24, time), TRACK_TTL)
     }
+}
 
-    pub fn get_underlying_path(&self) -> String {
-        self.resolve_path(&None)
+impl Source {
+    pub fn new(path: String, use_cache: bool, cache_ttl: Duration) -> Self {
+        Self { path, use_cache, cache_ttl }
+    }
+
+    pub fn get_path(&self) -> String {
+        self.path.clone()
     }
 
     pub fn should_use_cache(&self) -> bool { return self.use_cache; }
+    pub fn cache_ttl(&self) -> Duration { self.cache_ttl }
 }
 
 impl Clone for Source {
     fn clone(&self) -> Self {
-        Self { path: self.path.clone(), cred: self.cred.clone(), use_cache: self.use_cache }
+        Self { path: self.path.clone(), use_cache: self.use_cache, cache_ttl: self.cache_ttl }
     }
 }
 
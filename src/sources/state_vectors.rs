@@ -0,0 +1,95 @@
+use std::fmt;
+use std::time::Duration;
+use crate::data::aircraft::AircraftData;
+use crate::geo::bounds::GeoBounds;
+use crate::sources::httpclient::{HttpError, RateLimit};
+
+// Any provider of aircraft state vectors (OpenSky, local receivers, test fixtures, ...)
+pub trait StateVectorSource: Send {
+    // Short human-readable name for the source, used in diagnostics
+    fn name(&self) -> String;
+
+    // Retrieve the latest snapshot of state vectors from this source.  Sources may ignore any
+    // parts of the query they cannot apply, e.g. a local receiver has no use for bounds
+    fn fetch(&mut self, query: &StateVectorQuery) -> Result<AircraftData, SourceError>;
+
+    // Snapshot times which can be navigated freely, for recorded sources.  Live sources have no
+    // timeline and always present the latest data
+    fn timeline(&self) -> Option<Timeline> { None }
+
+    // Request quota reported by the source at its last retrieval, if it imposes one
+    fn rate_limit(&self) -> Option<RateLimit> { None }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StateVectorQuery {
+    pub bounds: Option<GeoBounds>,          // Restrict to this area, or None for the entire globe
+    pub time: Option<isize>                 // Simulation time to retrieve, or None for the latest data
+}
+
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    times: Vec<isize>                       // Sorted time of each available snapshot
+}
+
+impl Timeline {
+    pub fn new(mut times: Vec<isize>) -> Self {
+        times.sort();
+        times.dedup();
+        Self { times }
+    }
+
+    pub fn start(&self) -> isize { self.times.first().cloned().unwrap_or(0) }
+    pub fn end(&self) -> isize { self.times.last().cloned().unwrap_or(0) }
+
+    // Time of the snapshot `offset` snapshots away from the one current at `time`, limited to the
+    // extent of the timeline
+    pub fn step(&self, time: isize, offset: isize) -> Option<isize> {
+        if self.times.is_empty() { return None; }
+
+        let current = self.times.iter().take_while(|&&x| x <= time).count().max(1) as isize - 1;
+        let target = (current + offset).max(0).min(self.times.len() as isize - 1);
+        self.times.get(target as usize).cloned()
+    }
+}
+
+#[derive(Debug)]
+pub enum SourceError {
+    Http(HttpError),
+    RateLimited(Option<Duration>),          // Source has refused requests; retry after this delay, if known
+    Cache(std::io::Error),
+    File(std::io::Error),
+    Json(serde_json::error::Error)
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceError::Http(e) => write!(f, "Remote retrieval error: {}", e),
+            SourceError::RateLimited(Some(delay)) => write!(f, "Rate limited, retry in {}s", delay.as_secs()),
+            SourceError::RateLimited(None) => write!(f, "Rate limited"),
+            SourceError::Cache(e) => write!(f, "Cache retrieval error: {}", e),
+            SourceError::File(e) => write!(f, "File retrieval error: {}", e),
+            SourceError::Json(e) => write!(f, "Failed to deserialise response: {}", e)
+        }
+    }
+}
+
+impl From<HttpError> for SourceError {
+    fn from(error: HttpError) -> SourceError {
+        match error {
+            HttpError::RateLimited(delay) => SourceError::RateLimited(delay),
+            _ => SourceError::Http(error)
+        }
+    }
+}
+impl From<std::io::Error> for SourceError {
+    fn from(error: std::io::Error) -> SourceError {
+        SourceError::Cache(error)
+    }
+}
+impl From<serde_json::error::Error> for SourceError {
+    fn from(error: serde_json::error::Error) -> SourceError {
+        SourceError::Json(error)
+    }
+}
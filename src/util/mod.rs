@@ -1,2 +1,3 @@
 pub mod functional;
 pub mod temporal;
+pub mod units;
@@ -0,0 +1,149 @@
+use serde::*;
+
+// Conversions from the aviation units used by most receivers to the SI units of the data model
+
+const METRES_PER_FOOT: f32 = 0.3048;
+const METRES_PER_SEC_PER_KNOT: f32 = 1852.0 / 3600.0;
+const METRES_PER_SEC_PER_KM_PER_HOUR: f32 = 1000.0 / 3600.0;
+const METRES_PER_NAUTICAL_MILE: f32 = 1852.0;
+
+pub fn feet_to_metres(feet: f32) -> f32 {
+    feet * METRES_PER_FOOT
+}
+
+fn knots_to_metres_per_sec(knots: f32) -> f32 {
+    knots * METRES_PER_SEC_PER_KNOT
+}
+
+fn feet_per_min_to_metres_per_sec(fpm: f32) -> f32 {
+    (fpm * METRES_PER_FOOT) / 60.0
+}
+
+// Units in which quantities are displayed; all are held internally in SI units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnitSystem {
+    Aviation,                               // ft, kt, ft/min, nm
+    Metric                                  // m, km/h, m/s, km
+}
+
+// Altitude above mean sea level
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Altitude(f32);                   // Meters
+
+// Ground speed
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Speed(f32);                      // m/s
+
+// Rate of climb, positive means climbing
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct VerticalRate(f32);               // m/s
+
+// Distance over the ground
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Distance(f32);                   // Meters
+
+impl UnitSystem {
+    pub fn parse(name: &str) -> Option<UnitSystem> {
+        match name.to_ascii_lowercase().as_str() {
+            "aviation" | "imperial" => Some(UnitSystem::Aviation),
+            "metric" | "si" => Some(UnitSystem::Metric),
+            _ => None
+        }
+    }
+
+    pub fn next(&self) -> UnitSystem {
+        match self {
+            UnitSystem::Aviation => UnitSystem::Metric,
+            UnitSystem::Metric => UnitSystem::Aviation
+        }
+    }
+}
+
+impl Altitude {
+    pub fn from_metres(metres: f32) -> Self { Self(metres) }
+    pub fn from_feet(feet: f32) -> Self { Self(feet_to_metres(feet)) }
+
+    pub fn metres(&self) -> f32 { self.0 }
+    pub fn feet(&self) -> f32 { self.0 / METRES_PER_FOOT }
+
+    pub fn format(&self, units: UnitSystem) -> String {
+        match units {
+            UnitSystem::Aviation => format!("{:.0}ft", self.feet()),
+            UnitSystem::Metric => format!("{:.0}m", self.metres())
+        }
+    }
+}
+
+impl Speed {
+    #[cfg(test)]
+    pub fn from_metres_per_sec(mps: f32) -> Self { Self(mps) }
+    pub fn from_knots(knots: f32) -> Self { Self(knots_to_metres_per_sec(knots)) }
+    #[cfg(test)]
+    pub fn from_km_per_hour(kph: f32) -> Self { Self(kph * METRES_PER_SEC_PER_KM_PER_HOUR) }
+
+    pub fn metres_per_sec(&self) -> f32 { self.0 }
+    pub fn knots(&self) -> f32 { self.0 / METRES_PER_SEC_PER_KNOT }
+    pub fn km_per_hour(&self) -> f32 { self.0 / METRES_PER_SEC_PER_KM_PER_HOUR }
+
+    pub fn format(&self, units: UnitSystem) -> String {
+        match units {
+            UnitSystem::Aviation => format!("{:.0}kt", self.knots()),
+            UnitSystem::Metric => format!("{:.0}km/h", self.km_per_hour())
+        }
+    }
+}
+
+impl VerticalRate {
+    #[cfg(test)]
+    pub fn from_metres_per_sec(mps: f32) -> Self { Self(mps) }
+    pub fn from_feet_per_min(fpm: f32) -> Self { Self(feet_per_min_to_metres_per_sec(fpm)) }
+
+    pub fn metres_per_sec(&self) -> f32 { self.0 }
+    pub fn feet_per_min(&self) -> f32 { self.0 * 60.0 / METRES_PER_FOOT }
+
+    pub fn format(&self, units: UnitSystem) -> String {
+        match units {
+            UnitSystem::Aviation => format!("{:+.0}ft/min", self.feet_per_min()),
+            UnitSystem::Metric => format!("{:+.1}m/s", self.metres_per_sec())
+        }
+    }
+}
+
+impl Distance {
+    pub fn from_metres(metres: f32) -> Self { Self(metres) }
+    #[cfg(test)]
+    pub fn from_nautical_miles(nm: f32) -> Self { Self(nm * METRES_PER_NAUTICAL_MILE) }
+
+    pub fn nautical_miles(&self) -> f32 { self.0 / METRES_PER_NAUTICAL_MILE }
+    pub fn km(&self) -> f32 { self.0 / 1000.0 }
+
+    pub fn format(&self, units: UnitSystem) -> String {
+        match units {
+            UnitSystem::Aviation => format!("{:.0}nm", self.nautical_miles()),
+            UnitSystem::Metric => format!("{:.0}km", self.km())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Altitude, Speed, VerticalRate, Distance, UnitSystem};
+
+    #[test]
+    fn test_conversions() {
+        assert!((Altitude::from_feet(36000.0).metres() - 10972.8).abs() < 0.01);
+        assert!((Altitude::from_metres(10972.8).feet() - 36000.0).abs() < 0.01);
+        assert!((Speed::from_knots(450.0).metres_per_sec() - 231.5).abs() < 0.01);
+        assert!((Speed::from_km_per_hour(900.0).metres_per_sec() - 250.0).abs() < 0.01);
+        assert!((Speed::from_metres_per_sec(250.0).knots() - 485.96).abs() < 0.01);
+        assert!((VerticalRate::from_feet_per_min(-1024.0).metres_per_sec() - -5.2019).abs() < 0.001);
+
+        assert_eq!(Altitude::from_feet(36000.0).format(UnitSystem::Aviation), "36000ft");
+        assert_eq!(Altitude::from_feet(36000.0).format(UnitSystem::Metric), "10973m");
+        assert_eq!(Speed::from_metres_per_sec(250.0).format(UnitSystem::Metric), "900km/h");
+        assert_eq!(VerticalRate::from_feet_per_min(-1024.0).format(UnitSystem::Aviation), "-1024ft/min");
+        assert_eq!(VerticalRate::from_feet_per_min(1024.0).format(UnitSystem::Metric), "+5.2m/s");
+        assert_eq!(Distance::from_nautical_miles(100.0).format(UnitSystem::Metric), "185km");
+        assert_eq!(Distance::from_metres(5_540_000.0).format(UnitSystem::Aviation), "2991nm");
+    }
+}